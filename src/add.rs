@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::label::check_label;
+use crate::magnet::MagnetLink;
+use crate::metainfo::Metainfo;
+use crate::request::Ids;
+use crate::torrent::{bytes_to_metadata, TorrentAdd, TorrentAddArgs, TorrentSetArgs};
+use crate::tracker::TrackerList;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    Filename(String),
+    Metainfo(Vec<u8>),
+}
+
+// Collects every torrent-add option and checks them together in build
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TorrentAddBuilder {
+    sources: Vec<Source>,
+    cookies: Option<String>,
+    download_dir: Option<String>,
+    paused: Option<bool>,
+    peer_limit: Option<i64>,
+    bandwidth_priority: Option<i64>,
+    labels: Vec<String>,
+    bandwidth_group: Option<String>,
+    files_wanted: Vec<i64>,
+    files_unwanted: Vec<i64>,
+    priority_high: Vec<i64>,
+    priority_low: Vec<i64>,
+    priority_normal: Vec<i64>,
+}
+
+// Errors about the torrent itself, as opposed to errors reaching the daemon:
+// retrying the same input can't fix them
+pub fn is_rejection(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::BadResponse(_)
+            | Error::BothFileMeta
+            | Error::NoFileMeta
+            | Error::Bencode(_)
+            | Error::WrongMetainfo(_)
+            | Error::WrongMagnet(_)
+            | Error::WrongAddArgs(_)
+            | Error::WrongLabel(_)
+    )
+}
+
+fn wrong(message: &str) -> Error {
+    Error::WrongAddArgs(message.to_string())
+}
+
+fn non_empty(indices: Vec<i64>) -> Option<Vec<i64>> {
+    (!indices.is_empty()).then_some(indices)
+}
+
+// Every index must be a file of the torrent and appear in at most one of the lists
+fn check_disjoint(lists: &[&[i64]], file_count: Option<usize>) -> Result<(), Error> {
+    let mut seen = HashSet::new();
+    for index in lists.iter().flat_map(|list| list.iter()) {
+        if *index < 0 || file_count.is_some_and(|count| *index as usize >= count) {
+            return Err(wrong(&format!("no file with index {}", index)));
+        }
+        if !seen.insert(*index) {
+            return Err(wrong(&format!("file {} is in two lists", index)));
+        }
+    }
+    Ok(())
+}
+
+impl TorrentAddArgs {
+    pub fn builder() -> TorrentAddBuilder {
+        TorrentAddBuilder::default()
+    }
+}
+
+impl TorrentAddBuilder {
+    pub fn new() -> Self {
+        TorrentAddBuilder::default()
+    }
+
+    // A magnet link or an url or path the daemon can open
+    pub fn filename(mut self, filename: &str) -> Self {
+        self.sources.push(Source::Filename(filename.to_string()));
+        self
+    }
+
+    pub fn magnet(self, magnet: &MagnetLink) -> Self {
+        self.filename(&magnet.to_string())
+    }
+
+    // Raw .torrent bytes, base64 encoded by build
+    pub fn metainfo(mut self, data: &[u8]) -> Self {
+        self.sources.push(Source::Metainfo(data.to_vec()));
+        self
+    }
+
+    pub fn cookies(mut self, cookies: &str) -> Self {
+        self.cookies = Some(cookies.to_string());
+        self
+    }
+
+    pub fn download_dir(mut self, download_dir: &str) -> Self {
+        self.download_dir = Some(download_dir.to_string());
+        self
+    }
+
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = Some(paused);
+        self
+    }
+
+    pub fn peer_limit(mut self, peer_limit: i64) -> Self {
+        self.peer_limit = Some(peer_limit);
+        self
+    }
+
+    // -1 low, 0 normal, 1 high
+    pub fn bandwidth_priority(mut self, priority: i64) -> Self {
+        self.bandwidth_priority = Some(priority);
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        if !self.labels.iter().any(|known| known == label) {
+            self.labels.push(label.to_string());
+        }
+        self
+    }
+
+    pub fn labels(self, labels: &[&str]) -> Self {
+        labels
+            .iter()
+            .fold(self, |builder, label| builder.label(label))
+    }
+
+    // torrent-add has no bandwidth group argument, so build leaves it out and add sets it
+    // on the new torrent with a second request
+    pub fn bandwidth_group(mut self, group: &str) -> Self {
+        self.bandwidth_group = Some(group.to_string());
+        self
+    }
+
+    pub fn files_wanted(mut self, indices: &[i64]) -> Self {
+        self.files_wanted.extend_from_slice(indices);
+        self
+    }
+
+    pub fn files_unwanted(mut self, indices: &[i64]) -> Self {
+        self.files_unwanted.extend_from_slice(indices);
+        self
+    }
+
+    pub fn priority_high(mut self, indices: &[i64]) -> Self {
+        self.priority_high.extend_from_slice(indices);
+        self
+    }
+
+    pub fn priority_low(mut self, indices: &[i64]) -> Self {
+        self.priority_low.extend_from_slice(indices);
+        self
+    }
+
+    pub fn priority_normal(mut self, indices: &[i64]) -> Self {
+        self.priority_normal.extend_from_slice(indices);
+        self
+    }
+
+    pub fn build(self) -> Result<TorrentAddArgs, Error> {
+        self.build_with_group().map(|(args, _)| args)
+    }
+
+    fn build_with_group(self) -> Result<(TorrentAddArgs, Option<String>), Error> {
+        let mut sources = self.sources.into_iter();
+        let (source, extra) = (sources.next(), sources.next());
+        let (filename, metainfo, file_count) = match (source, extra) {
+            (None, _) => return Err(Error::NoFileMeta),
+            (Some(Source::Filename(_)), Some(Source::Metainfo(_)))
+            | (Some(Source::Metainfo(_)), Some(Source::Filename(_))) => {
+                return Err(Error::BothFileMeta)
+            }
+            (Some(_), Some(_)) => return Err(wrong("more than one torrent")),
+            (Some(Source::Filename(filename)), None) => {
+                if filename.trim().is_empty() {
+                    return Err(wrong("empty filename"));
+                }
+                (Some(filename), None, None)
+            }
+            (Some(Source::Metainfo(data)), None) => {
+                // Indices can only be checked against the file list when the metainfo is known.
+                // The daemon leaves pad files out of it.
+                let file_count = Metainfo::from_bytes(&data)?
+                    .files
+                    .iter()
+                    .filter(|file| !file.padding)
+                    .count();
+                (None, Some(bytes_to_metadata(&data)?), Some(file_count))
+            }
+        };
+        if self.peer_limit.is_some_and(|limit| limit <= 0) {
+            return Err(wrong("peer limit must be positive"));
+        }
+        if self
+            .bandwidth_priority
+            .is_some_and(|priority| !(-1..=1).contains(&priority))
+        {
+            return Err(wrong("bandwidth priority must be -1, 0 or 1"));
+        }
+        for label in &self.labels {
+            check_label(label)?;
+        }
+        if self
+            .bandwidth_group
+            .as_deref()
+            .is_some_and(|group| group.trim().is_empty())
+        {
+            return Err(wrong("empty bandwidth group"));
+        }
+        check_disjoint(&[&self.files_wanted, &self.files_unwanted], file_count)?;
+        check_disjoint(
+            &[
+                &self.priority_high,
+                &self.priority_low,
+                &self.priority_normal,
+            ],
+            file_count,
+        )?;
+        let args = TorrentAddArgs {
+            cookies: self.cookies,
+            download_dir: self.download_dir,
+            filename,
+            labels: (!self.labels.is_empty()).then_some(self.labels),
+            metainfo,
+            paused: self.paused,
+            peer_limit: self.peer_limit,
+            bandwidth_priority: self.bandwidth_priority,
+            files_wanted: non_empty(self.files_wanted),
+            files_unwanted: non_empty(self.files_unwanted),
+            priority_high: non_empty(self.priority_high),
+            priority_low: non_empty(self.priority_low),
+            priority_normal: non_empty(self.priority_normal),
+        };
+        Ok((args, self.bandwidth_group))
+    }
+
+    // Builds and adds the torrent, then puts a newly added one in the bandwidth group
+    pub async fn add(self, client: &mut Client) -> Result<BuilderAdd, Error> {
+        let (args, group) = self.build_with_group()?;
+        let added = client.torrent_add(args).await?;
+        let group = match (group, &added) {
+            (Some(group), TorrentAdd::Added(torrent)) => Some(
+                client
+                    .set_bandwidth_group(Ids::Id(torrent.id), &group)
+                    .await,
+            ),
+            _ => None,
+        };
+        Ok(BuilderAdd { added, group })
+    }
+}
+
+// The torrent stays added when joining the bandwidth group fails, so the two results are kept apart
+#[derive(Debug)]
+pub struct BuilderAdd {
+    pub added: TorrentAdd,
+    // None when no group was asked for or the torrent was a duplicate
+    pub group: Option<Result<(), Error>>,
+}
+
+impl TorrentAddArgs {
+    // Trackers the added torrent will have, as far as they can be told from here:
+    // those of the metainfo or of a magnet filename
+    pub fn trackers(&self) -> TrackerList {
+        if let Some(metainfo) = &self.metainfo {
+            let metainfo = STANDARD
+                .decode(metainfo)
+                .ok()
+                .and_then(|data| Metainfo::from_bytes(&data).ok());
+            return TrackerList {
+                tiers: metainfo
+                    .map(|metainfo| metainfo.trackers)
+                    .unwrap_or_default(),
+            };
+        }
+        let magnet = self
+            .filename
+            .as_deref()
+            .and_then(|filename| filename.parse::<MagnetLink>().ok());
+        TrackerList {
+            tiers: magnet
+                .map(|magnet| magnet.trackers.into_iter().map(|url| vec![url]).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Client {
+    pub async fn set_bandwidth_group(&mut self, ids: Ids, group: &str) -> Result<(), Error> {
+        self.torrent_set(TorrentSetArgs {
+            ids,
+            group: Some(group.to_string()),
+            ..TorrentSetArgs::default()
+        })
+        .await
+    }
+
+    // Adds the torrent, and when the daemon already has it merges the trackers and labels
+    // of args into the existing torrent instead
+    pub async fn add_or_merge(&mut self, args: TorrentAddArgs) -> Result<TorrentAdd, Error> {
+        let trackers = args.trackers();
+        let labels = args.labels.clone().unwrap_or_default();
+        let added = self.torrent_add(args).await?;
+        if let TorrentAdd::Duplicate(existing) = &added {
+            if !trackers.is_empty() {
+                self.merge_trackers(existing.id, &trackers).await?;
+            }
+            if !labels.is_empty() {
+                let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+                self.add_labels(Ids::Id(existing.id), &labels).await?;
+            }
+        }
+        Ok(added)
+    }
+}
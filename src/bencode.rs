@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match self {
+            Value::Dict(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_dict()?.get(key.as_bytes())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+
+    // Dictionaries come out with sorted keys, which is the canonical form
+    pub fn encode_to(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Integer(value) => out.extend_from_slice(format!("i{}e", value).as_bytes()),
+            Value::Bytes(value) => {
+                out.extend_from_slice(format!("{}:", value.len()).as_bytes());
+                out.extend_from_slice(value);
+            }
+            Value::List(list) => {
+                out.push(b'l');
+                for item in list {
+                    item.encode_to(out);
+                }
+                out.push(b'e');
+            }
+            Value::Dict(dict) => {
+                out.push(b'd');
+                for (key, value) in dict {
+                    out.extend_from_slice(format!("{}:", key.len()).as_bytes());
+                    out.extend_from_slice(key);
+                    value.encode_to(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Bytes(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Bytes(value.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytes(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::List(value)
+    }
+}
+
+impl From<BTreeMap<Vec<u8>, Value>> for Value {
+    fn from(value: BTreeMap<Vec<u8>, Value>) -> Self {
+        Value::Dict(value)
+    }
+}
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    value.encode()
+}
+
+fn bencode_error(message: &str, pos: usize) -> Error {
+    Error::Bencode(format!("{} at byte {}", message, pos))
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn peek(&self) -> Result<u8, Error> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| bencode_error("unexpected end", self.pos))
+    }
+
+    fn read_until(&mut self, end: u8) -> Result<&'a [u8], Error> {
+        let start = self.pos;
+        let len = self.data[start..]
+            .iter()
+            .position(|byte| *byte == end)
+            .ok_or_else(|| bencode_error("unexpected end", self.data.len()))?;
+        self.pos = start + len + 1;
+        Ok(&self.data[start..start + len])
+    }
+
+    // Only canonical integers: no leading zeros, no negative zero, no plus sign
+    fn integer(&mut self, end: u8) -> Result<i64, Error> {
+        let start = self.pos;
+        let digits = self.read_until(end)?;
+        let text = std::str::from_utf8(digits).map_err(|_| bencode_error("bad integer", start))?;
+        let unsigned = text.strip_prefix('-').unwrap_or(text);
+        if unsigned.is_empty()
+            || !unsigned.bytes().all(|byte| byte.is_ascii_digit())
+            || (unsigned.len() > 1 && unsigned.starts_with('0'))
+            || text == "-0"
+        {
+            return Err(bencode_error("bad integer", start));
+        }
+        text.parse()
+            .map_err(|_| bencode_error("integer overflow", start))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let start = self.pos;
+        let len = self.integer(b':')?;
+        if len < 0 {
+            return Err(bencode_error("negative length", start));
+        }
+        let end = self
+            .pos
+            .checked_add(len as usize)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| bencode_error("string past the end", start))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(bencode_error("nesting too deep", self.pos));
+        }
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                Ok(Value::Integer(self.integer(b'e')?))
+            }
+            b'l' => {
+                self.pos += 1;
+                let mut list = Vec::new();
+                while self.peek()? != b'e' {
+                    list.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::List(list))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut dict = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let start = self.pos;
+                    let key = self.bytes()?.to_vec();
+                    let value = self.value(depth + 1)?;
+                    if dict.insert(key, value).is_some() {
+                        return Err(bencode_error("duplicate key", start));
+                    }
+                }
+                self.pos += 1;
+                Ok(Value::Dict(dict))
+            }
+            b'0'..=b'9' => Ok(Value::Bytes(self.bytes()?.to_vec())),
+            _ => Err(bencode_error("unexpected byte", self.pos)),
+        }
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Value, Error> {
+    let mut decoder = Decoder { data, pos: 0 };
+    let value = decoder.value(0)?;
+    if decoder.pos != data.len() {
+        return Err(bencode_error("trailing data", decoder.pos));
+    }
+    Ok(value)
+}
+
+// The raw bytes of one value of the top level dictionary, as they appear in the input.
+// Info hashes must be taken over these bytes rather than over a re-encoded value.
+pub fn raw_dict_value<'a>(data: &'a [u8], key: &str) -> Result<Option<&'a [u8]>, Error> {
+    let mut decoder = Decoder { data, pos: 0 };
+    if decoder.peek()? != b'd' {
+        return Err(bencode_error("not a dictionary", 0));
+    }
+    decoder.pos += 1;
+    while decoder.peek()? != b'e' {
+        let current = decoder.bytes()?;
+        let start = decoder.pos;
+        decoder.value(1)?;
+        if current == key.as_bytes() {
+            return Ok(Some(&data[start..decoder.pos]));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+
+use crate::add::is_rejection;
+use crate::client::Client;
+use crate::error::Error;
+use crate::magnet::MagnetLink;
+use crate::torrent::{bytes_to_metadata, TorrentAdd, TorrentAddArgs, TorrentAddResponse};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkSource {
+    Magnet(String),
+    File(PathBuf),
+}
+
+impl fmt::Display for BulkSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BulkSource::Magnet(magnet) => write!(f, "{}", magnet),
+            BulkSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BulkOutcome {
+    Added(TorrentAddResponse),
+    Duplicate(TorrentAddResponse),
+    Failed(Error),
+}
+
+impl From<Result<TorrentAdd, Error>> for BulkOutcome {
+    fn from(result: Result<TorrentAdd, Error>) -> Self {
+        match result {
+            Ok(TorrentAdd::Added(response)) => BulkOutcome::Added(response),
+            Ok(TorrentAdd::Duplicate(response)) => BulkOutcome::Duplicate(response),
+            Err(err) => BulkOutcome::Failed(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BulkItem {
+    pub source: BulkSource,
+    pub outcome: BulkOutcome,
+}
+
+// Adds many magnets and .torrent files, every item gets the template arguments
+#[derive(Debug, Clone)]
+pub struct BulkAdd {
+    sources: Vec<BulkSource>,
+    template: TorrentAddArgs,
+    concurrency: usize,
+}
+
+impl Default for BulkAdd {
+    fn default() -> Self {
+        BulkAdd::new()
+    }
+}
+
+impl BulkAdd {
+    pub fn new() -> Self {
+        BulkAdd {
+            sources: Vec::new(),
+            template: TorrentAddArgs::default(),
+            concurrency: 4,
+        }
+    }
+
+    pub fn source(mut self, source: BulkSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    // One magnet per line, blank lines and # comments are skipped
+    pub fn magnets(mut self, text: &str) -> Self {
+        self.sources.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| BulkSource::Magnet(line.to_string())),
+        );
+        self
+    }
+
+    pub fn magnet_file<P: AsRef<Path>>(self, path: P) -> Result<Self, Error> {
+        Ok(self.magnets(&std::fs::read_to_string(path)?))
+    }
+
+    // Every .torrent file directly in dir, by name
+    pub fn torrent_dir<P: AsRef<Path>>(mut self, dir: P) -> Result<Self, Error> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_torrent = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("torrent"));
+            if is_torrent && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        self.sources.extend(paths.into_iter().map(BulkSource::File));
+        Ok(self)
+    }
+
+    // Download dir, paused, labels and the rest; filename and metainfo are set per item
+    pub fn template(mut self, template: TorrentAddArgs) -> Self {
+        self.template = template;
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn sources(&self) -> &[BulkSource] {
+        &self.sources
+    }
+
+    async fn add_args(&self, source: &BulkSource) -> Result<TorrentAddArgs, Error> {
+        let mut args = self.template.clone();
+        args.filename = None;
+        args.cookies = None;
+        args.metainfo = None;
+        match source {
+            BulkSource::Magnet(magnet) => {
+                magnet.parse::<MagnetLink>()?;
+                args.filename = Some(magnet.clone());
+            }
+            BulkSource::File(path) => {
+                args.metainfo = Some(bytes_to_metadata(&tokio::fs::read(path).await?)?);
+            }
+        }
+        Ok(args)
+    }
+}
+
+// Rejected input and unreadable local files fail the same way on every run, network
+// errors reach the daemon as Error::Nc and may pass later
+fn is_permanent(err: &Error) -> bool {
+    is_rejection(err) || matches!(err, Error::Io(_))
+}
+
+#[derive(Debug, Serialize)]
+struct ReportItem<'a> {
+    source: String,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+    #[serde(rename = "hashString", skip_serializing_if = "Option::is_none")]
+    hash_string: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    // Failures that may pass on a later run, like connection errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    added: usize,
+    duplicate: usize,
+    failed: usize,
+    items: Vec<ReportItem<'a>>,
+}
+
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    pub items: Vec<BulkItem>,
+}
+
+impl BulkReport {
+    pub fn added(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.outcome, BulkOutcome::Added(_)))
+    }
+
+    pub fn duplicates(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.outcome, BulkOutcome::Duplicate(_)))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.outcome, BulkOutcome::Failed(_)))
+    }
+
+    // Counts and one entry per item in source order
+    pub fn to_json(&self) -> Result<String, Error> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let (status, response, err) = match &item.outcome {
+                    BulkOutcome::Added(response) => ("added", Some(response), None),
+                    BulkOutcome::Duplicate(response) => ("duplicate", Some(response), None),
+                    BulkOutcome::Failed(err) => ("failed", None, Some(err)),
+                };
+                ReportItem {
+                    source: item.source.to_string(),
+                    status,
+                    id: response.map(|response| response.id),
+                    hash_string: response.map(|response| response.hash_string.as_str()),
+                    name: response.map(|response| response.name.as_str()),
+                    error: err.map(|err| err.to_string()),
+                    retry: err.map(|err| !is_permanent(err)),
+                }
+            })
+            .collect();
+        Ok(serde_json::to_string(&Report {
+            added: self.added().count(),
+            duplicate: self.duplicates().count(),
+            failed: self.failed().count(),
+            items,
+        })?)
+    }
+}
+
+impl Client {
+    // Runs up to bulk.concurrency adds at a time, each on its own copy of the client.
+    // A failed item never stops the others.
+    pub async fn bulk_add(&self, bulk: &BulkAdd) -> BulkReport {
+        let items = stream::iter(bulk.sources.iter())
+            .map(|source| {
+                let mut client = self.clone();
+                async move {
+                    let result = match bulk.add_args(source).await {
+                        Ok(args) => client.torrent_add(args).await,
+                        Err(err) => Err(err),
+                    };
+                    BulkItem {
+                        source: source.clone(),
+                        outcome: result.into(),
+                    }
+                }
+            })
+            .buffered(bulk.concurrency)
+            .collect()
+            .await;
+        BulkReport { items }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::time::{sleep, Instant};
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids, RECENTLY_ACTIVE_WINDOW};
+use crate::torrent::{Torrent, TorrentFields, TorrentGet, TorrentGetArgs};
+
+// Copies every field the update carries over the target, fields the update lacks keep their value
+pub fn merge_torrent(target: &mut Torrent, update: Torrent) -> Result<(), Error> {
+    let mut merged = serde_json::to_value(&*target)?;
+    if let (Value::Object(merged), Value::Object(update)) =
+        (&mut merged, serde_json::to_value(update)?)
+    {
+        for (key, value) in update {
+            if !value.is_null() {
+                merged.insert(key, value);
+            }
+        }
+    }
+    *target = serde_json::from_value(merged)?;
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    torrents: HashMap<i64, Arc<Torrent>>,
+    hashes: HashMap<String, i64>,
+    generation: u64,
+    loaded: bool,
+    // When the request behind the last load or update was sent
+    updated: Option<Instant>,
+}
+
+impl CacheState {
+    fn insert(&mut self, id: i64, torrent: Torrent) {
+        if let Some(hash) = &torrent.hash_string {
+            self.hashes.insert(hash.to_lowercase(), id);
+        }
+        self.torrents.insert(id, Arc::new(torrent));
+    }
+
+    fn remove(&mut self, id: i64) {
+        if let Some(torrent) = self.torrents.remove(&id) {
+            if let Some(hash) = &torrent.hash_string {
+                self.hashes.remove(&hash.to_lowercase());
+            }
+        }
+    }
+}
+
+// Clones share the same state, so one task can keep it updated while others read snapshots
+#[derive(Debug, Clone, Default)]
+pub struct TorrentCache {
+    state: Arc<RwLock<CacheState>>,
+}
+
+impl TorrentCache {
+    pub fn new() -> Self {
+        TorrentCache::default()
+    }
+
+    // A panicked writer leaves whole entries behind, so a poisoned lock is still readable
+    fn read(&self) -> RwLockReadGuard<'_, CacheState> {
+        self.state.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, CacheState> {
+        self.state.write().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn get(&self, id: i64) -> Option<Arc<Torrent>> {
+        self.read().torrents.get(&id).cloned()
+    }
+
+    pub fn get_by_hash(&self, hash: &str) -> Option<Arc<Torrent>> {
+        let state = self.read();
+        let id = state.hashes.get(&hash.to_lowercase())?;
+        state.torrents.get(id).cloned()
+    }
+
+    pub fn get_by_id(&self, id: &Id) -> Option<Arc<Torrent>> {
+        match id {
+            Id::Id(id) => self.get(*id),
+            Id::Hash(hash) => self.get_by_hash(hash),
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<Arc<Torrent>> {
+        let mut torrents: Vec<Arc<Torrent>> = self.read().torrents.values().cloned().collect();
+        torrents.sort_by_key(|torrent| torrent.id);
+        torrents
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().torrents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().torrents.is_empty()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.read().loaded
+    }
+
+    // Bumped on every applied response, readers can compare it to skip unchanged redraws
+    pub fn generation(&self) -> u64 {
+        self.read().generation
+    }
+
+    pub fn replace_all(&self, torrents: Vec<Torrent>) {
+        let mut state = self.write();
+        state.torrents.clear();
+        state.hashes.clear();
+        for torrent in torrents {
+            if let Some(id) = torrent.id {
+                state.insert(id, torrent);
+            }
+        }
+        state.loaded = true;
+        state.generation += 1;
+    }
+
+    // Every torrent is merged before the first one is stored, a failed merge changes nothing
+    pub fn apply(&self, update: TorrentGet) -> Result<(), Error> {
+        let mut state = self.write();
+        let mut merged = Vec::new();
+        for torrent in update.torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            match state.torrents.get(&id) {
+                Some(current) => {
+                    let mut current = Torrent::clone(current);
+                    merge_torrent(&mut current, torrent)?;
+                    merged.push((id, current));
+                }
+                None => merged.push((id, torrent)),
+            }
+        }
+        for (id, torrent) in merged {
+            state.insert(id, torrent);
+        }
+        for id in update.removed.unwrap_or_default() {
+            state.remove(id);
+        }
+        state.generation += 1;
+        Ok(())
+    }
+
+    pub async fn load(&self, client: &mut Client, fields: &[TorrentFields]) -> Result<(), Error> {
+        let started = Instant::now();
+        let response = client
+            .torrent_get(TorrentGetArgs {
+                ids: None,
+                fields: with_id(fields),
+            })
+            .await?;
+        self.replace_all(response.torrents);
+        self.write().updated = Some(started);
+        Ok(())
+    }
+
+    // Loads everything the first time and whenever the last update is older than the daemon
+    // remembers recently-active changes, otherwise only merges the recently-active torrents
+    pub async fn update(&self, client: &mut Client, fields: &[TorrentFields]) -> Result<(), Error> {
+        let started = Instant::now();
+        let fresh = match self.read().updated {
+            Some(updated) => started - updated < RECENTLY_ACTIVE_WINDOW,
+            None => false,
+        };
+        if !self.is_loaded() || !fresh {
+            return self.load(client, fields).await;
+        }
+        let response = client
+            .torrent_get(TorrentGetArgs {
+                ids: Some(Ids::RecentlyActive),
+                fields: with_id(fields),
+            })
+            .await?;
+        self.apply(response)?;
+        self.write().updated = Some(started);
+        Ok(())
+    }
+
+    // Keeps the cache updated until a request fails
+    pub async fn run(
+        &self,
+        mut client: Client,
+        fields: Vec<TorrentFields>,
+        interval: Duration,
+    ) -> Result<(), Error> {
+        loop {
+            self.update(&mut client, &fields).await?;
+            sleep(interval).await;
+        }
+    }
+}
+
+fn with_id(fields: &[TorrentFields]) -> Vec<TorrentFields> {
+    let mut with_id = vec![TorrentFields::Id, TorrentFields::HashString];
+    for field in fields {
+        if !with_id.contains(field) {
+            with_id.push(*field);
+        }
+    }
+    with_id
+}
@@ -4,10 +4,13 @@ use netc::StatusCode;
 use crate::error::Error;
 use crate::request::RpcRequest;
 use crate::response::RpcResponse;
+use crate::session::{SessionFields, SessionGetArgs};
 
+#[derive(Clone)]
 pub struct Client {
     uri: String,
     id: String,
+    rpc_version: Option<i64>,
 }
 
 impl Client {
@@ -15,9 +18,24 @@ impl Client {
         Client {
             uri: uri.to_string(),
             id: String::new(),
+            rpc_version: None,
         }
     }
 
+    pub async fn rpc_version(&mut self) -> Result<i64, Error> {
+        if let Some(version) = self.rpc_version {
+            return Ok(version);
+        }
+        let session = self
+            .session_get(Some(SessionGetArgs {
+                fields: vec![SessionFields::RpcVersion],
+            }))
+            .await?;
+        let version = session.rpc_version.ok_or(Error::NoArguments)?;
+        self.rpc_version = Some(version);
+        Ok(version)
+    }
+
     fn set_id(&mut self, id: String) -> &mut Client {
         self.id = id;
         self
@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use url::Url;
+
+use crate::error::Error;
+use crate::torrent::TorrentAddArgs;
+use crate::util::now;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    // Unix time, 0 for session cookies
+    pub expires: i64,
+    pub name: String,
+    pub value: String,
+}
+
+impl Cookie {
+    fn domain_matches(&self, host: &str) -> bool {
+        let domain = self.domain.trim_start_matches('.').to_lowercase();
+        host == domain
+            || (self.include_subdomains
+                && host
+                    .strip_suffix(&domain)
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    }
+
+    // RFC 6265 path-match: same path, or a prefix ending at a "/" boundary
+    fn path_matches(&self, path: &str) -> bool {
+        path == self.path
+            || (path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')))
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires != 0 && self.expires <= now
+    }
+
+    pub fn matches(&self, url: &str, now: i64) -> bool {
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+        // Cookie files write ipv6 hosts without the brackets
+        let host = url
+            .host_str()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        self.domain_matches(&host)
+            && self.path_matches(url.path())
+            && (url.scheme() == "https" || !self.secure)
+            && !self.is_expired(now)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    // Netscape cookies.txt: domain, subdomains flag, path, secure flag, expiry, name, value,
+    // separated by tabs. Curl marks http-only cookies with a #HttpOnly_ domain prefix.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut cookies = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let wrong = || Error::WrongCookie(format!("line {}: {}", number + 1, line));
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 7 && fields.len() != 6 {
+                return Err(wrong());
+            }
+            let flag = |value: &str| match value.to_uppercase().as_str() {
+                "TRUE" => Ok(true),
+                "FALSE" => Ok(false),
+                _ => Err(wrong()),
+            };
+            cookies.push(Cookie {
+                domain: fields[0].to_string(),
+                include_subdomains: flag(fields[1])?,
+                path: fields[2].to_string(),
+                secure: flag(fields[3])?,
+                http_only,
+                expires: fields[4].parse().map_err(|_| wrong())?,
+                name: fields[5].to_string(),
+                value: fields.get(6).unwrap_or(&"").to_string(),
+            });
+        }
+        Ok(CookieJar { cookies })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        CookieJar::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    pub fn push(&mut self, cookie: Cookie) {
+        self.cookies.push(cookie);
+    }
+
+    // Cookies to send to url at the given time, longer paths first like browsers do
+    pub fn matching_at(&self, url: &str, now: i64) -> Vec<&Cookie> {
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(url, now))
+            .collect();
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        cookies
+    }
+
+    pub fn matching(&self, url: &str) -> Vec<&Cookie> {
+        self.matching_at(url, now())
+    }
+
+    // The NAME=CONTENTS; NAME=CONTENTS string TorrentAddArgs.cookies expects
+    pub fn header_at(&self, url: &str, now: i64) -> Option<String> {
+        let cookies = self.matching_at(url, now);
+        if cookies.is_empty() {
+            return None;
+        }
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    pub fn header(&self, url: &str) -> Option<String> {
+        self.header_at(url, now())
+    }
+}
+
+impl TorrentAddArgs {
+    // Fills cookies for the filename url, leaves them alone when nothing matches.
+    // The daemon sends them on to every host it is redirected to, TorrentFetch::cookie_jar
+    // picks them per url instead.
+    pub fn cookies_from_jar(mut self, jar: &CookieJar) -> Self {
+        if let Some(cookies) = self
+            .filename
+            .as_deref()
+            .and_then(|filename| jar.header(filename))
+        {
+            self.cookies = Some(cookies);
+        }
+        self
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::bencode::Value;
+use crate::error::Error;
+use crate::torrent::TorrentAddArgs;
+use crate::util::now;
+
+const BLOCK_SIZE: usize = 16 * 1024;
+const MIN_PIECE_LENGTH: i64 = 16 * 1024;
+const MAX_PIECE_LENGTH: i64 = 16 * 1024 * 1024;
+// Automatic piece lengths grow until the torrent has at most this many pieces
+const TARGET_PIECES: u64 = 1500;
+
+fn wrong(message: &str) -> Error {
+    Error::CreateTorrent(message.to_string())
+}
+
+fn dict(entries: Vec<(&str, Value)>) -> Value {
+    Value::Dict(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.as_bytes().to_vec(), value))
+            .collect(),
+    )
+}
+
+fn path_value(parts: &[String]) -> Value {
+    Value::List(
+        parts
+            .iter()
+            .map(|part| Value::from(part.as_str()))
+            .collect(),
+    )
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    path: PathBuf,
+    parts: Vec<String>,
+    length: u64,
+}
+
+// Symlinks inside the directory are skipped, they may loop or lead out of it
+fn scan_dir(dir: &Path, prefix: &[String], files: &mut Vec<SourceFile>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| wrong("file name is not utf-8"))?;
+        let mut parts = prefix.to_vec();
+        parts.push(name);
+        let metadata = std::fs::symlink_metadata(entry.path())?;
+        if metadata.is_dir() {
+            scan_dir(&entry.path(), &parts, files)?;
+        } else if metadata.is_file() {
+            files.push(SourceFile {
+                path: entry.path(),
+                parts,
+                length: metadata.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+struct V1Hasher {
+    piece_length: usize,
+    hasher: Sha1,
+    filled: usize,
+    pieces: Vec<u8>,
+}
+
+impl V1Hasher {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = (self.piece_length - self.filled).min(data.len());
+            self.hasher.update(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+            if self.filled == self.piece_length {
+                self.pieces.extend_from_slice(&self.hasher.finalize_reset());
+                self.filled = 0;
+            }
+        }
+    }
+
+    // Zero bytes up to the next piece boundary, the size of the padding file
+    fn pad(&mut self) -> usize {
+        if self.filled == 0 {
+            return 0;
+        }
+        let padding = self.piece_length - self.filled;
+        self.update(&vec![0; padding]);
+        padding
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.filled > 0 {
+            self.pieces.extend_from_slice(&self.hasher.finalize());
+        }
+        self.pieces
+    }
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+// Root of a tree whose leaves are padded with `pad` up to a power of two
+fn merkle_root(mut layer: Vec<[u8; 32]>, pad: [u8; 32]) -> [u8; 32] {
+    layer.resize(layer.len().next_power_of_two(), pad);
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    layer[0]
+}
+
+// BEP 52 pieces root and, for files longer than one piece, the piece layer
+fn v2_file_hashes(leaves: Vec<[u8; 32]>, blocks_per_piece: usize) -> ([u8; 32], Option<Vec<u8>>) {
+    if leaves.len() <= blocks_per_piece {
+        return (merkle_root(leaves, [0; 32]), None);
+    }
+    let pieces: Vec<[u8; 32]> = leaves
+        .chunks(blocks_per_piece)
+        .map(|chunk| {
+            let mut chunk = chunk.to_vec();
+            chunk.resize(blocks_per_piece, [0; 32]);
+            merkle_root(chunk, [0; 32])
+        })
+        .collect();
+    let pad = merkle_root(vec![[0; 32]; blocks_per_piece], [0; 32]);
+    let layer = pieces.concat();
+    (merkle_root(pieces, pad), Some(layer))
+}
+
+fn read_block(file: &mut File, buf: &mut [u8]) -> Result<usize, Error> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = file.read(&mut buf[filled..])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+fn insert_tree(tree: &mut BTreeMap<Vec<u8>, Value>, parts: &[String], leaf: Value) {
+    let Some((first, rest)) = parts.split_first() else {
+        return;
+    };
+    let key = first.as_bytes().to_vec();
+    if rest.is_empty() {
+        tree.insert(key, dict(vec![("", leaf)]));
+        return;
+    }
+    let node = tree
+        .entry(key)
+        .or_insert_with(|| Value::Dict(BTreeMap::new()));
+    if let Value::Dict(node) = node {
+        insert_tree(node, rest, leaf);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TorrentCreator {
+    path: PathBuf,
+    name: Option<String>,
+    piece_length: Option<i64>,
+    trackers: Vec<Vec<String>>,
+    web_seeds: Vec<String>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    creation_date: Option<i64>,
+    private: bool,
+    source: Option<String>,
+    hybrid: bool,
+}
+
+impl TorrentCreator {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        TorrentCreator {
+            path: path.as_ref().to_path_buf(),
+            name: None,
+            piece_length: None,
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            comment: None,
+            created_by: Some(format!("trpc/{}", env!("CARGO_PKG_VERSION"))),
+            creation_date: Some(now()),
+            private: false,
+            source: None,
+            hybrid: false,
+        }
+    }
+
+    // Defaults to the file or directory name
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    // A power of two of at least 16 KiB, picked from the total size when not set
+    pub fn piece_length(mut self, piece_length: i64) -> Self {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    // Each call adds a tier
+    pub fn tracker_tier(mut self, urls: &[&str]) -> Self {
+        self.trackers
+            .push(urls.iter().map(|url| url.to_string()).collect());
+        self
+    }
+
+    pub fn tracker(self, url: &str) -> Self {
+        self.tracker_tier(&[url])
+    }
+
+    pub fn web_seed(mut self, url: &str) -> Self {
+        self.web_seeds.push(url.to_string());
+        self
+    }
+
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    pub fn created_by(mut self, created_by: Option<&str>) -> Self {
+        self.created_by = created_by.map(|created_by| created_by.to_string());
+        self
+    }
+
+    // Unix time, defaults to now
+    pub fn creation_date(mut self, creation_date: Option<i64>) -> Self {
+        self.creation_date = creation_date;
+        self
+    }
+
+    pub fn private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    // Adds BitTorrent v2 hashes next to the v1 ones, files get aligned to pieces with padding
+    pub fn hybrid(mut self, hybrid: bool) -> Self {
+        self.hybrid = hybrid;
+        self
+    }
+
+    fn files(&self) -> Result<(String, bool, Vec<SourceFile>), Error> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => self
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| wrong("path has no utf-8 file name"))?
+                .to_string(),
+        };
+        let metadata = std::fs::metadata(&self.path)?;
+        if metadata.is_file() {
+            let file = SourceFile {
+                path: self.path.clone(),
+                parts: vec![name.clone()],
+                length: metadata.len(),
+            };
+            return Ok((name, false, vec![file]));
+        }
+        let mut files = Vec::new();
+        scan_dir(&self.path, &[], &mut files)?;
+        if files.is_empty() {
+            return Err(wrong("directory has no files"));
+        }
+        // Same order as the keys of the v2 file tree
+        files.sort_by(|a, b| a.parts.cmp(&b.parts));
+        Ok((name, true, files))
+    }
+
+    fn pick_piece_length(&self, total: u64) -> Result<i64, Error> {
+        match self.piece_length {
+            Some(length) if length < MIN_PIECE_LENGTH || !(length as u64).is_power_of_two() => Err(
+                wrong("piece length must be a power of two of at least 16 KiB"),
+            ),
+            Some(length) => Ok(length),
+            None => {
+                let mut length = MIN_PIECE_LENGTH;
+                while length < MAX_PIECE_LENGTH && total / length as u64 > TARGET_PIECES {
+                    length *= 2;
+                }
+                Ok(length)
+            }
+        }
+    }
+
+    // Hashes the files and returns the bencoded .torrent
+    pub fn create(&self) -> Result<Vec<u8>, Error> {
+        let (name, multi_file, files) = self.files()?;
+        let total: u64 = files.iter().map(|file| file.length).sum();
+        let piece_length = self.pick_piece_length(total)?;
+        let blocks_per_piece = piece_length as usize / BLOCK_SIZE;
+        let mut v1 = V1Hasher {
+            piece_length: piece_length as usize,
+            hasher: Sha1::new(),
+            filled: 0,
+            pieces: Vec::new(),
+        };
+        let mut v1_files = Vec::new();
+        let mut file_tree = BTreeMap::new();
+        let mut piece_layers = BTreeMap::new();
+        let mut buf = vec![0; BLOCK_SIZE];
+        for (index, source) in files.iter().enumerate() {
+            if self.hybrid && index > 0 {
+                let padding = v1.pad();
+                if padding > 0 {
+                    v1_files.push(dict(vec![
+                        ("attr", Value::from("p")),
+                        ("length", Value::from(padding as i64)),
+                        (
+                            "path",
+                            path_value(&[".pad".to_string(), padding.to_string()]),
+                        ),
+                    ]));
+                }
+            }
+            let mut file = File::open(&source.path)?;
+            let mut leaves = Vec::new();
+            let mut read_total = 0;
+            loop {
+                let read = read_block(&mut file, &mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                v1.update(&buf[..read]);
+                if self.hybrid {
+                    leaves.push(Sha256::digest(&buf[..read]).into());
+                }
+                read_total += read as u64;
+            }
+            if read_total != source.length {
+                return Err(wrong("file changed while hashing"));
+            }
+            let length = Value::from(source.length as i64);
+            v1_files.push(dict(vec![
+                ("length", length.clone()),
+                ("path", path_value(&source.parts)),
+            ]));
+            if self.hybrid {
+                let mut leaf = vec![("length", length)];
+                if !leaves.is_empty() {
+                    let (root, layer) = v2_file_hashes(leaves, blocks_per_piece);
+                    if let Some(layer) = layer {
+                        piece_layers.insert(root.to_vec(), Value::Bytes(layer));
+                    }
+                    leaf.push(("pieces root", Value::Bytes(root.to_vec())));
+                }
+                insert_tree(&mut file_tree, &source.parts, dict(leaf));
+            }
+        }
+
+        let mut info = vec![
+            ("name", Value::from(name.as_str())),
+            ("piece length", Value::from(piece_length)),
+            ("pieces", Value::Bytes(v1.finish())),
+            ("private", Value::from(i64::from(self.private))),
+        ];
+        if multi_file {
+            info.push(("files", Value::List(v1_files)));
+        } else {
+            info.push(("length", Value::from(files[0].length as i64)));
+        }
+        if self.hybrid {
+            info.push(("file tree", Value::Dict(file_tree)));
+            info.push(("meta version", Value::from(2)));
+        }
+        if let Some(source) = &self.source {
+            info.push(("source", Value::from(source.as_str())));
+        }
+
+        let mut root = vec![("info", dict(info))];
+        let trackers: Vec<Vec<String>> = self
+            .trackers
+            .iter()
+            .filter(|tier| !tier.is_empty())
+            .cloned()
+            .collect();
+        if let Some(first) = trackers.first().and_then(|tier| tier.first()) {
+            root.push(("announce", Value::from(first.as_str())));
+        }
+        if trackers.iter().flatten().count() > 1 {
+            let tiers = trackers.iter().map(|tier| path_value(tier)).collect();
+            root.push(("announce-list", Value::List(tiers)));
+        }
+        if !self.web_seeds.is_empty() {
+            root.push(("url-list", path_value(&self.web_seeds)));
+        }
+        if let Some(comment) = &self.comment {
+            root.push(("comment", Value::from(comment.as_str())));
+        }
+        if let Some(created_by) = &self.created_by {
+            root.push(("created by", Value::from(created_by.as_str())));
+        }
+        if let Some(creation_date) = self.creation_date {
+            root.push(("creation date", Value::from(creation_date)));
+        }
+        if self.hybrid {
+            root.push(("piece layers", Value::Dict(piece_layers)));
+        }
+        Ok(dict(root).encode())
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, Error> {
+        let data = self.create()?;
+        std::fs::write(path, &data)?;
+        Ok(data)
+    }
+
+    // Metainfo add arguments that make the daemon seed the data where it already is,
+    // which only works when the daemon sees the same filesystem. The daemon looks for the
+    // data under the torrent name, so it has to be the name on disk.
+    pub fn add_args(&self) -> Result<TorrentAddArgs, Error> {
+        let path = std::env::current_dir()?.join(&self.path);
+        let on_disk = path.file_name().and_then(|name| name.to_str());
+        if self.name.is_some() && self.name.as_deref() != on_disk {
+            return Err(wrong("name differs from the name on disk"));
+        }
+        let data = self.create()?;
+        let download_dir = match path.parent() {
+            Some(parent) => std::fs::canonicalize(parent)?
+                .to_str()
+                .map(|parent| parent.to_string()),
+            None => None,
+        };
+        Ok(TorrentAddArgs {
+            download_dir,
+            metainfo: Some(STANDARD.encode(data)),
+            ..TorrentAddArgs::default()
+        })
+    }
+}
@@ -22,4 +22,46 @@ pub enum Error {
     NoFileMeta,
     #[error("Unknown torrent fields")]
     UnknownTorrentFields,
+    #[error("wrong schedule time: {0}")]
+    WrongScheduleTime(String),
+    #[error("wrong schedule days: {0}")]
+    WrongScheduleDays(i64),
+    #[error("wrong unit value: {0}")]
+    WrongUnitValue(String),
+    #[error("tracker tiers need rpc version 17, daemon has {0}")]
+    TrackerTierUnsupported(i64),
+    #[error("regex error")]
+    Regex(#[from] regex::Error),
+    #[error("wrong label: {0}")]
+    WrongLabel(String),
+    #[error("torrent not found")]
+    TorrentNotFound,
+    #[error("unknown torrent status: {0}")]
+    UnknownTorrentStatus(i64),
+    #[error("timed out waiting for torrent")]
+    Timeout,
+    #[error("bencode error: {0}")]
+    Bencode(String),
+    #[error("wrong metainfo: {0}")]
+    WrongMetainfo(String),
+    #[error("wrong magnet link: {0}")]
+    WrongMagnet(String),
+    #[error("can't create torrent: {0}")]
+    CreateTorrent(String),
+    #[error("fetching torrent failed with http status {0}")]
+    FetchStatus(u16),
+    #[error("fetching torrent timed out")]
+    FetchTimeout,
+    #[error("fetching torrent took more than {0} redirects")]
+    FetchRedirects(usize),
+    #[error("fetched torrent is larger than {0} bytes")]
+    FetchTooLarge(usize),
+    #[error("wrong url: {0}")]
+    WrongUrl(String),
+    #[error("wrong cookie: {0}")]
+    WrongCookie(String),
+    #[error("wrong torrent-add args: {0}")]
+    WrongAddArgs(String),
+    #[error("wrong pieces bitfield: {0}")]
+    WrongBitfield(String),
 }
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use netc::{HttpStream, Method, Request, Response};
+use tokio::io::AsyncReadExt;
+use tokio::time::timeout;
+use url::Url;
+
+use crate::client::Client;
+use crate::cookies::CookieJar;
+use crate::error::Error;
+use crate::metainfo::Metainfo;
+use crate::torrent::{bytes_to_metadata, TorrentAdd, TorrentAddArgs};
+
+// Downloads a .torrent on this side of the connection, for daemons that can't reach the url
+#[derive(Debug, Clone)]
+pub struct TorrentFetch {
+    url: String,
+    headers: Vec<(String, String)>,
+    cookies: Option<String>,
+    jar: Option<CookieJar>,
+    max_redirects: usize,
+    max_size: usize,
+    timeout: Duration,
+}
+
+const HEADERS_MAX_LENGTH: usize = 16384;
+
+impl TorrentFetch {
+    pub fn new(url: &str) -> Self {
+        TorrentFetch {
+            url: url.to_string(),
+            headers: Vec::new(),
+            cookies: None,
+            jar: None,
+            max_redirects: 10,
+            max_size: 16 * 1024 * 1024,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // Same NAME=CONTENTS; NAME=CONTENTS format as TorrentAddArgs.cookies
+    pub fn cookies(mut self, cookies: &str) -> Self {
+        self.cookies = Some(cookies.to_string());
+        self
+    }
+
+    // Cookies are picked from the jar again for every url on the redirect chain
+    pub fn cookie_jar(mut self, jar: &CookieJar) -> Self {
+        self.jar = Some(jar.clone());
+        self
+    }
+
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Largest body accepted, .torrent files are rarely more than a few MiB
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    // The body is read here instead of by netc to stop at max_size
+    async fn read_body(
+        &self,
+        stream: &mut HttpStream,
+        response: &Response,
+    ) -> Result<Vec<u8>, Error> {
+        let too_large = || Error::FetchTooLarge(self.max_size);
+        if !response.has_body() {
+            return Ok(Vec::new());
+        }
+        let mut body = Vec::new();
+        if response.has_chuncked_body() {
+            loop {
+                let size = stream.read_chunk_line().await?;
+                if size == 0 {
+                    break;
+                }
+                if body.len() + size > self.max_size {
+                    return Err(too_large());
+                }
+                let start = body.len();
+                body.resize(start + size, 0);
+                stream.read_exact(&mut body[start..]).await?;
+                stream.read_exact(&mut [0; 2]).await?;
+            }
+        } else if let Some(length) = response.content_len() {
+            if length > self.max_size {
+                return Err(too_large());
+            }
+            body.resize(length, 0);
+            stream.read_exact(&mut body).await?;
+        } else {
+            stream
+                .take(self.max_size as u64 + 1)
+                .read_to_end(&mut body)
+                .await?;
+            if body.len() > self.max_size {
+                return Err(too_large());
+            }
+        }
+        Ok(body)
+    }
+
+    // One request without following redirects. Cookies and custom headers only go to
+    // the origin of the fetch url, never to a host a redirect points at.
+    async fn get(&self, url: &Url, same_origin: bool) -> Result<(Response, Vec<u8>), Error> {
+        let mut request = Request::new(Method::Get, url);
+        request.header("Accept", "application/x-bittorrent, */*");
+        let mut cookies = Vec::new();
+        if same_origin {
+            for (name, value) in &self.headers {
+                request.header(name, value);
+            }
+            cookies.extend(self.cookies.clone());
+        }
+        cookies.extend(self.jar.as_ref().and_then(|jar| jar.header(url.as_str())));
+        if !cookies.is_empty() {
+            request.header("Cookie", &cookies.join("; "));
+        }
+        let mut stream = HttpStream::from_request(&request).await?;
+        stream.send_msg(&request.to_vec()).await?;
+        let mut head = Vec::with_capacity(512);
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await?);
+            if head.len() > HEADERS_MAX_LENGTH {
+                return Err(Error::Nc(netc::Error::HeaderToBig));
+            }
+        }
+        let response = Response::from_header(&head)?;
+        let body = self.read_body(&mut stream, &response).await?;
+        Ok((response, body))
+    }
+
+    async fn get_following(&self) -> Result<(Response, Vec<u8>), Error> {
+        let start = Url::parse(&self.url).map_err(|err| Error::WrongUrl(err.to_string()))?;
+        let mut url = start.clone();
+        let mut redirects = 0;
+        loop {
+            let (response, body) = self.get(&url, url.origin() == start.origin()).await?;
+            let location = response.header("Location");
+            match location {
+                Some(location) if response.status_code().is_redirect() => {
+                    redirects += 1;
+                    if redirects > self.max_redirects {
+                        return Err(Error::FetchRedirects(self.max_redirects));
+                    }
+                    url = url
+                        .join(&location)
+                        .map_err(|err| Error::WrongUrl(err.to_string()))?;
+                }
+                _ => return Ok((response, body)),
+            }
+        }
+    }
+
+    // The body of the final response after redirects, checked to be a valid .torrent
+    pub async fn fetch(&self) -> Result<Vec<u8>, Error> {
+        let (response, body) = timeout(self.timeout, self.get_following())
+            .await
+            .map_err(|_| Error::FetchTimeout)??;
+        if !response.status_code().is_success() {
+            return Err(Error::FetchStatus(response.status_code().into()));
+        }
+        Metainfo::from_bytes(&body)?;
+        Ok(body)
+    }
+
+    pub async fn fetch_metainfo(&self) -> Result<Metainfo, Error> {
+        Metainfo::from_bytes(&self.fetch().await?)
+    }
+}
+
+impl TorrentAddArgs {
+    // Fetch settings for an http(s) filename, taking its cookies along
+    pub fn remote_fetch(&self) -> Option<TorrentFetch> {
+        let url = self.filename.as_deref()?;
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return None;
+        }
+        let fetch = TorrentFetch::new(url);
+        Some(match &self.cookies {
+            Some(cookies) => fetch.cookies(cookies),
+            None => fetch,
+        })
+    }
+}
+
+impl Client {
+    // Downloads the .torrent with fetch and adds it as metainfo, keeping the other arguments
+    pub async fn torrent_add_fetched(
+        &mut self,
+        fetch: &TorrentFetch,
+        mut args: TorrentAddArgs,
+    ) -> Result<TorrentAdd, Error> {
+        let data = fetch.fetch().await?;
+        args.filename = None;
+        args.cookies = None;
+        args.metainfo = Some(bytes_to_metadata(&data)?);
+        self.torrent_add(args).await
+    }
+
+    // Like torrent_add, but http(s) filenames are fetched here instead of by the daemon
+    pub async fn torrent_add_local_fetch(
+        &mut self,
+        args: TorrentAddArgs,
+    ) -> Result<TorrentAdd, Error> {
+        match args.remote_fetch() {
+            Some(fetch) => self.torrent_add_fetched(&fetch, args).await,
+            None => self.torrent_add(args).await,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids};
+use crate::torrent::{
+    Torrent, TorrentFields, TorrentGetArgs, TorrentRemoveArgs, TorrentRenamePath,
+    TorrentRenamePathArgs, TorrentSetArgs, TorrentSetLocationArgs,
+};
+
+pub struct TorrentHandle<'a> {
+    client: &'a mut Client,
+    id: Id,
+}
+
+impl Client {
+    pub fn torrent<I: Into<Id>>(&mut self, id: I) -> TorrentHandle<'_> {
+        TorrentHandle {
+            client: self,
+            id: id.into(),
+        }
+    }
+}
+
+impl<'a> TorrentHandle<'a> {
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+
+    pub fn ids(&self) -> Ids {
+        self.id.clone().into()
+    }
+
+    pub fn client(&mut self) -> &mut Client {
+        self.client
+    }
+
+    pub async fn start(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.torrent_start(Some(ids)).await
+    }
+
+    pub async fn start_now(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.torrent_start_now(Some(ids)).await
+    }
+
+    pub async fn stop(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.torrent_stop(Some(ids)).await
+    }
+
+    pub async fn verify(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.torrent_verify(Some(ids)).await
+    }
+
+    pub async fn reannounce(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.torrent_reannounce(Some(ids)).await
+    }
+
+    pub async fn set_location(
+        &mut self,
+        location: &str,
+        move_local_data: bool,
+    ) -> Result<(), Error> {
+        let args = TorrentSetLocationArgs {
+            ids: self.ids(),
+            location: location.to_string(),
+            move_local_data,
+        };
+        self.client.torrent_set_location(args).await
+    }
+
+    pub async fn rename(&mut self, path: &str, name: &str) -> Result<TorrentRenamePath, Error> {
+        let args = TorrentRenamePathArgs {
+            ids: self.ids(),
+            path: path.to_string(),
+            name: name.to_string(),
+        };
+        self.client.torrent_rename_path(args).await
+    }
+
+    pub async fn remove(self, delete_local_data: bool) -> Result<(), Error> {
+        let args = TorrentRemoveArgs {
+            ids: self.ids(),
+            delete_local_data,
+        };
+        self.client.torrent_remove(args).await
+    }
+
+    // The ids of the passed args are replaced by this torrent
+    pub async fn set(&mut self, args: TorrentSetArgs) -> Result<(), Error> {
+        let args = TorrentSetArgs {
+            ids: self.ids(),
+            ..args
+        };
+        self.client.torrent_set(args).await
+    }
+
+    // Limits are in KBps, None removes the limit
+    pub async fn set_limits(
+        &mut self,
+        download_limit: Option<i64>,
+        upload_limit: Option<i64>,
+    ) -> Result<(), Error> {
+        self.set(TorrentSetArgs {
+            download_limit,
+            download_limited: Some(download_limit.is_some()),
+            upload_limit,
+            upload_limited: Some(upload_limit.is_some()),
+            ..TorrentSetArgs::default()
+        })
+        .await
+    }
+
+    pub async fn set_bandwidth_priority(&mut self, priority: i64) -> Result<(), Error> {
+        self.set(TorrentSetArgs {
+            bandwidth_priority: Some(priority),
+            ..TorrentSetArgs::default()
+        })
+        .await
+    }
+
+    pub async fn set_seed_ratio_limit(&mut self, ratio: Option<f64>) -> Result<(), Error> {
+        // seedRatioMode: 0 follows the session, 1 uses the torrent limit, 2 seeds unlimited
+        self.set(TorrentSetArgs {
+            seed_ratio_limit: ratio,
+            seed_ratio_mode: Some(if ratio.is_some() { 1 } else { 2 }),
+            ..TorrentSetArgs::default()
+        })
+        .await
+    }
+
+    pub async fn queue_move_top(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.queue_move_top(Some(ids)).await
+    }
+
+    pub async fn queue_move_up(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.queue_move_up(Some(ids)).await
+    }
+
+    pub async fn queue_move_down(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.queue_move_down(Some(ids)).await
+    }
+
+    pub async fn queue_move_bottom(&mut self) -> Result<(), Error> {
+        let ids = self.ids();
+        self.client.queue_move_bottom(Some(ids)).await
+    }
+
+    pub async fn add_labels(&mut self, labels: &[&str]) -> Result<bool, Error> {
+        let ids = self.ids();
+        Ok(!self.client.add_labels(ids, labels).await?.is_empty())
+    }
+
+    pub async fn remove_labels(&mut self, labels: &[&str]) -> Result<bool, Error> {
+        let ids = self.ids();
+        Ok(!self.client.remove_labels(ids, labels).await?.is_empty())
+    }
+
+    pub async fn add_tracker(
+        &mut self,
+        announce: &str,
+        tier: Option<usize>,
+    ) -> Result<bool, Error> {
+        let ids = self.ids();
+        Ok(!self
+            .client
+            .add_tracker(ids, announce, tier)
+            .await?
+            .is_empty())
+    }
+
+    pub async fn remove_tracker(&mut self, announce: &str) -> Result<bool, Error> {
+        let ids = self.ids();
+        Ok(!self.client.remove_tracker(ids, announce).await?.is_empty())
+    }
+
+    pub async fn refresh(&mut self, fields: Vec<TorrentFields>) -> Result<Torrent, Error> {
+        let args = TorrentGetArgs {
+            ids: Some(self.ids()),
+            fields,
+        };
+        self.client
+            .torrent_get(args)
+            .await?
+            .torrents
+            .into_iter()
+            .next()
+            .ok_or(Error::TorrentNotFound)
+    }
+}
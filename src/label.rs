@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids};
+use crate::torrent::{Torrent, TorrentFields, TorrentGetArgs, TorrentSetArgs};
+
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+// The daemon splits labels on commas and drops empty ones
+pub fn check_label(label: &str) -> Result<(), Error> {
+    if label.trim().is_empty() || label.contains(',') {
+        return Err(Error::WrongLabel(label.to_string()));
+    }
+    Ok(())
+}
+
+// A key with the separator would be split at the wrong place when read back
+pub fn check_namespace_key(key: &str) -> Result<(), Error> {
+    if key.is_empty() || key.contains(NAMESPACE_SEPARATOR) {
+        return Err(Error::WrongLabel(key.to_string()));
+    }
+    Ok(())
+}
+
+pub fn merge_labels(labels: &[String], add: &[&str]) -> Vec<String> {
+    let mut merged = labels.to_vec();
+    for label in add {
+        if !merged.iter().any(|current| current == label) {
+            merged.push(label.to_string());
+        }
+    }
+    merged
+}
+
+pub fn strip_labels(labels: &[String], remove: &[&str]) -> Vec<String> {
+    labels
+        .iter()
+        .filter(|label| !remove.contains(&label.as_str()))
+        .cloned()
+        .collect()
+}
+
+pub fn rename_label(labels: &[String], old: &str, new: &str) -> Vec<String> {
+    let mut renamed = Vec::new();
+    for label in labels {
+        let label = if label == old { new } else { label.as_str() };
+        if !renamed.iter().any(|current| current == label) {
+            renamed.push(label.to_string());
+        }
+    }
+    renamed
+}
+
+pub fn split_label(label: &str) -> Option<(&str, &str)> {
+    let (key, value) = label.split_once(NAMESPACE_SEPARATOR)?;
+    if key.is_empty() {
+        return None;
+    }
+    Some((key, value))
+}
+
+pub fn namespaced_label(key: &str, value: &str) -> String {
+    format!("{}{}{}", key, NAMESPACE_SEPARATOR, value)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelNamespaces {
+    pub namespaces: BTreeMap<String, Vec<String>>,
+    pub plain: Vec<String>,
+}
+
+impl LabelNamespaces {
+    pub fn parse(labels: &[String]) -> Self {
+        let mut parsed = LabelNamespaces::default();
+        for label in labels {
+            match split_label(label) {
+                Some((key, value)) => parsed
+                    .namespaces
+                    .entry(key.to_string())
+                    .or_default()
+                    .push(value.to_string()),
+                None => parsed.plain.push(label.clone()),
+            }
+        }
+        parsed
+    }
+
+    pub fn from_torrent(torrent: &Torrent) -> Self {
+        LabelNamespaces::parse(torrent.labels.as_deref().unwrap_or_default())
+    }
+
+    pub fn values(&self, key: &str) -> &[String] {
+        self.namespaces
+            .get(key)
+            .map(|values| values.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values(key).first().map(|value| value.as_str())
+    }
+
+    pub fn contains(&self, key: &str, value: &str) -> bool {
+        self.values(key).iter().any(|current| current == value)
+    }
+}
+
+// Drops every label of the namespace and appends the new values, other labels keep their order
+pub fn set_namespace(labels: &[String], key: &str, values: &[&str]) -> Vec<String> {
+    let mut updated: Vec<String> = labels
+        .iter()
+        .filter(|label| !matches!(split_label(label), Some((current, _)) if current == key))
+        .cloned()
+        .collect();
+    for value in values {
+        let label = namespaced_label(key, value);
+        if !updated.contains(&label) {
+            updated.push(label);
+        }
+    }
+    updated
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NamespaceFilter {
+    Has(String),
+    Missing(String),
+    Equals(String, String),
+    AnyOf(String, Vec<String>),
+}
+
+impl NamespaceFilter {
+    pub fn matches(&self, namespaces: &LabelNamespaces) -> bool {
+        match self {
+            NamespaceFilter::Has(key) => !namespaces.values(key).is_empty(),
+            NamespaceFilter::Missing(key) => namespaces.values(key).is_empty(),
+            NamespaceFilter::Equals(key, value) => namespaces.contains(key, value),
+            NamespaceFilter::AnyOf(key, values) => {
+                values.iter().any(|value| namespaces.contains(key, value))
+            }
+        }
+    }
+
+    pub fn matches_torrent(&self, torrent: &Torrent) -> bool {
+        self.matches(&LabelNamespaces::from_torrent(torrent))
+    }
+}
+
+impl Client {
+    pub async fn add_labels(&mut self, ids: Ids, labels: &[&str]) -> Result<Vec<i64>, Error> {
+        for label in labels {
+            check_label(label)?;
+        }
+        self.update_labels(ids, |current| merge_labels(current, labels))
+            .await
+    }
+
+    pub async fn remove_labels(&mut self, ids: Ids, labels: &[&str]) -> Result<Vec<i64>, Error> {
+        self.update_labels(ids, |current| strip_labels(current, labels))
+            .await
+    }
+
+    pub async fn rename_label(
+        &mut self,
+        ids: Ids,
+        old: &str,
+        new: &str,
+    ) -> Result<Vec<i64>, Error> {
+        check_label(new)?;
+        self.update_labels(ids, |current| rename_label(current, old, new))
+            .await
+    }
+
+    pub async fn set_label_namespace(
+        &mut self,
+        ids: Ids,
+        key: &str,
+        values: &[&str],
+    ) -> Result<Vec<i64>, Error> {
+        check_namespace_key(key)?;
+        for value in values {
+            check_label(&namespaced_label(key, value))?;
+        }
+        self.update_labels(ids, |current| set_namespace(current, key, values))
+            .await
+    }
+
+    pub async fn clear_label_namespace(&mut self, ids: Ids, key: &str) -> Result<Vec<i64>, Error> {
+        check_namespace_key(key)?;
+        self.update_labels(ids, |current| set_namespace(current, key, &[]))
+            .await
+    }
+
+    pub async fn torrents_by_namespace(
+        &mut self,
+        ids: Option<Ids>,
+        filter: &NamespaceFilter,
+        mut fields: Vec<TorrentFields>,
+    ) -> Result<Vec<Torrent>, Error> {
+        if !fields.contains(&TorrentFields::Labels) {
+            fields.push(TorrentFields::Labels);
+        }
+        let torrents = self
+            .torrent_get(TorrentGetArgs { ids, fields })
+            .await?
+            .torrents;
+        Ok(torrents
+            .into_iter()
+            .filter(|torrent| filter.matches_torrent(torrent))
+            .collect())
+    }
+
+    // Reads the current labels, applies the update and writes back only the torrents that
+    // changed, one torrent-set per distinct resulting label list. Returns the changed ids.
+    pub async fn update_labels<F>(&mut self, ids: Ids, update: F) -> Result<Vec<i64>, Error>
+    where
+        F: Fn(&[String]) -> Vec<String>,
+    {
+        let torrents = self
+            .torrent_get(TorrentGetArgs {
+                ids: Some(ids),
+                fields: vec![TorrentFields::Id, TorrentFields::Labels],
+            })
+            .await?
+            .torrents;
+        let mut groups: BTreeMap<Vec<String>, Vec<i64>> = BTreeMap::new();
+        for torrent in torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            let current = torrent.labels.unwrap_or_default();
+            let updated = update(&current);
+            if updated != current {
+                groups.entry(updated).or_default().push(id);
+            }
+        }
+        let mut changed = Vec::new();
+        for (labels, ids) in groups {
+            self.torrent_set(TorrentSetArgs {
+                ids: Ids::Array(ids.iter().map(|id| Id::Id(*id)).collect()),
+                labels: Some(labels),
+                ..TorrentSetArgs::default()
+            })
+            .await?;
+            changed.extend(ids);
+        }
+        changed.sort_unstable();
+        Ok(changed)
+    }
+}
@@ -1,9 +1,31 @@
+pub mod add;
+pub mod bencode;
+pub mod bulk;
+pub mod cache;
 pub mod client;
+pub mod cookies;
+pub mod create;
 pub mod error;
+pub mod fetch;
+pub mod handle;
+pub mod label;
+pub mod magnet;
+pub mod metainfo;
+pub mod poller;
+pub mod query;
 pub mod request;
 pub mod response;
+pub mod rewrite;
+pub mod schedule;
 pub mod session;
 pub mod torrent;
+pub mod tracker;
+pub mod units;
+mod util;
+pub mod verify;
+pub mod wait;
+pub mod watch_folder;
+pub mod watcher;
 
 pub use crate::client::Client;
 pub use crate::error::Error;
@@ -0,0 +1,305 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::metainfo::Metainfo;
+use crate::request::Id;
+use crate::torrent::{Torrent, TorrentAddArgs};
+
+const BASE32: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+// More files than any real torrent has, keeps "so=0-99999999999" from exhausting memory
+const MAX_SELECT_ONLY: i64 = 100_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MagnetLink {
+    pub info_hash: Option<String>,
+    pub info_hash_v2: Option<String>,
+    pub display_name: Option<String>,
+    pub trackers: Vec<String>,
+    pub web_seeds: Vec<String>,
+    pub exact_length: Option<i64>,
+    pub select_only: Vec<i64>,
+}
+
+fn wrong(message: &str) -> Error {
+    Error::WrongMagnet(message.to_string())
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn base32_to_hex(value: &str) -> Option<String> {
+    let mut bits: u64 = 0;
+    let mut count = 0;
+    let mut hex = String::with_capacity(40);
+    for byte in value.bytes() {
+        let digit = BASE32
+            .iter()
+            .position(|base| *base == byte.to_ascii_uppercase())?;
+        bits = (bits << 5) | digit as u64;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            hex.push_str(&format!("{:02x}", (bits >> count) & 0xff));
+        }
+    }
+    Some(hex)
+}
+
+fn decode_component(value: &str) -> Result<String, Error> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .filter(|hex| is_hex(hex, 2))
+                    .ok_or_else(|| wrong("bad percent encoding"))?;
+                decoded
+                    .push(u8::from_str_radix(hex, 16).map_err(|_| wrong("bad percent encoding"))?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| wrong("parameter is not utf-8"))
+}
+
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// "so" holds file indices and inclusive ranges, e.g. 0,2,4-6
+fn parse_select_only(value: &str) -> Result<Vec<i64>, Error> {
+    let mut indices = Vec::new();
+    for part in value.split(',').filter(|part| !part.is_empty()) {
+        let bad = || wrong("bad select-only index");
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: i64 = start.parse().map_err(|_| bad())?;
+                let end: i64 = end.parse().map_err(|_| bad())?;
+                if start < 0 || end < start {
+                    return Err(bad());
+                }
+                if end - start >= MAX_SELECT_ONLY - indices.len() as i64 {
+                    return Err(wrong("too many select-only indices"));
+                }
+                indices.extend(start..=end);
+            }
+            None => {
+                let index: i64 = part.parse().map_err(|_| bad())?;
+                if index < 0 {
+                    return Err(bad());
+                }
+                if indices.len() as i64 >= MAX_SELECT_ONLY {
+                    return Err(wrong("too many select-only indices"));
+                }
+                indices.push(index);
+            }
+        }
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Ok(indices)
+}
+
+fn format_select_only(indices: &[i64]) -> String {
+    let mut parts = Vec::new();
+    let mut iter = indices.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        if start == end {
+            parts.push(start.to_string());
+        } else {
+            parts.push(format!("{}-{}", start, end));
+        }
+    }
+    parts.join(",")
+}
+
+impl MagnetLink {
+    pub fn from_metainfo(metainfo: &Metainfo) -> Self {
+        MagnetLink {
+            info_hash: metainfo.info_hash_v1(),
+            info_hash_v2: metainfo.info_hash_v2(),
+            display_name: Some(metainfo.name.clone()),
+            trackers: metainfo.announces().cloned().collect(),
+            web_seeds: metainfo.web_seeds.clone(),
+            exact_length: Some(metainfo.total_size()),
+            select_only: Vec::new(),
+        }
+    }
+
+    // Needs the hashString field, name, trackers, webseeds and totalSize are used when present
+    pub fn from_torrent(torrent: &Torrent) -> Result<Self, Error> {
+        let hash = torrent
+            .hash_string
+            .as_deref()
+            .ok_or_else(|| wrong("torrent without hashString"))?
+            .to_lowercase();
+        let mut magnet = MagnetLink {
+            display_name: torrent.name.clone(),
+            trackers: torrent
+                .trackers
+                .iter()
+                .flatten()
+                .map(|tracker| tracker.announce.clone())
+                .collect(),
+            web_seeds: torrent
+                .webseeds
+                .iter()
+                .flatten()
+                .map(|webseed| webseed.webseed.clone())
+                .collect(),
+            exact_length: torrent.total_size,
+            ..MagnetLink::default()
+        };
+        if is_hex(&hash, 40) {
+            magnet.info_hash = Some(hash);
+        } else if is_hex(&hash, 64) {
+            magnet.info_hash_v2 = Some(hash);
+        } else {
+            return Err(wrong("bad hashString"));
+        }
+        Ok(magnet)
+    }
+
+    // The hash the daemon reports as hashString: v1 when there is one, v2 otherwise
+    pub fn id(&self) -> Id {
+        Id::Hash(
+            self.info_hash
+                .clone()
+                .or_else(|| self.info_hash_v2.clone())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn set_topic(&mut self, topic: &str) -> Result<(), Error> {
+        if let Some(hash) = topic.strip_prefix("urn:btih:") {
+            let hash = if is_hex(hash, 40) {
+                hash.to_lowercase()
+            } else if hash.len() == 32 {
+                base32_to_hex(hash).ok_or_else(|| wrong("bad base32 btih"))?
+            } else {
+                return Err(wrong("bad btih length"));
+            };
+            self.info_hash = Some(hash);
+        } else if let Some(multihash) = topic.strip_prefix("urn:btmh:") {
+            // Only sha2-256 multihashes (code 0x12, 32 bytes) are defined for BitTorrent v2
+            match multihash.strip_prefix("1220") {
+                Some(hash) if is_hex(hash, 64) => self.info_hash_v2 = Some(hash.to_lowercase()),
+                _ => return Err(wrong("bad btmh")),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MagnetLink {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let query = s
+            .trim()
+            .strip_prefix("magnet:?")
+            .ok_or_else(|| wrong("missing magnet:? prefix"))?;
+        let mut magnet = MagnetLink::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode_component(value)?;
+            // Repeated parameters may be numbered: xt.1, tr.2
+            let key = key.split_once('.').map_or(key, |(key, _)| key);
+            match key {
+                "xt" => magnet.set_topic(&value)?,
+                "dn" => magnet.display_name = Some(value),
+                "tr" => magnet.trackers.push(value),
+                "ws" => magnet.web_seeds.push(value),
+                "xl" => {
+                    let length = value.parse().map_err(|_| wrong("bad exact length"))?;
+                    if length < 0 {
+                        return Err(wrong("bad exact length"));
+                    }
+                    magnet.exact_length = Some(length);
+                }
+                "so" => magnet.select_only = parse_select_only(&value)?,
+                _ => {}
+            }
+        }
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(wrong("missing btih or btmh topic"));
+        }
+        Ok(magnet)
+    }
+}
+
+impl TryFrom<&str> for MagnetLink {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = Vec::new();
+        if let Some(hash) = &self.info_hash {
+            params.push(format!("xt=urn:btih:{}", hash));
+        }
+        if let Some(hash) = &self.info_hash_v2 {
+            params.push(format!("xt=urn:btmh:1220{}", hash));
+        }
+        if let Some(name) = &self.display_name {
+            params.push(format!("dn={}", encode_component(name)));
+        }
+        if let Some(length) = self.exact_length {
+            params.push(format!("xl={}", length));
+        }
+        for tracker in &self.trackers {
+            params.push(format!("tr={}", encode_component(tracker)));
+        }
+        for web_seed in &self.web_seeds {
+            params.push(format!("ws={}", encode_component(web_seed)));
+        }
+        if !self.select_only.is_empty() {
+            params.push(format!("so={}", format_select_only(&self.select_only)));
+        }
+        write!(f, "magnet:?{}", params.join("&"))
+    }
+}
+
+impl From<&MagnetLink> for TorrentAddArgs {
+    fn from(magnet: &MagnetLink) -> Self {
+        TorrentAddArgs {
+            filename: Some(magnet.to_string()),
+            ..TorrentAddArgs::default()
+        }
+    }
+}
+
+impl From<MagnetLink> for TorrentAddArgs {
+    fn from(magnet: MagnetLink) -> Self {
+        TorrentAddArgs::from(&magnet)
+    }
+}
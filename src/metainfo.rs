@@ -0,0 +1,539 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::bencode::{self, Value};
+use crate::error::Error;
+use crate::request::Id;
+use crate::torrent::TorrentAddArgs;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetainfoFile {
+    pub path: Vec<String>,
+    pub length: i64,
+    pub padding: bool,
+}
+
+impl MetainfoFile {
+    pub fn path_string(&self) -> String {
+        self.path.join("/")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metainfo {
+    pub name: String,
+    pub files: Vec<MetainfoFile>,
+    pub multi_file: bool,
+    pub piece_length: i64,
+    pub pieces: Vec<u8>,
+    pub trackers: Vec<Vec<String>>,
+    pub private: bool,
+    pub creation_date: Option<i64>,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    pub web_seeds: Vec<String>,
+    pub source: Option<String>,
+    pub meta_version: Option<i64>,
+    info_bytes: Vec<u8>,
+}
+
+fn wrong(message: &str) -> Error {
+    Error::WrongMetainfo(message.to_string())
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    value
+        .and_then(|value| value.as_bytes())
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+}
+
+// Prefer the utf-8 variant of a key when the creator wrote both
+fn utf8_text(dict: &Value, key: &str) -> Option<String> {
+    text(dict.get(&format!("{}.utf-8", key))).or_else(|| text(dict.get(key)))
+}
+
+// Names are joined onto a local directory, so none may climb out of it or nest by itself
+pub(crate) fn safe_component(part: &str) -> bool {
+    !part.is_empty() && part != "." && part != ".." && !part.contains(['/', '\\', '\0'])
+}
+
+fn path_list(value: &Value) -> Result<Vec<String>, Error> {
+    let parts = value
+        .as_list()
+        .ok_or_else(|| wrong("file path is not a list"))?;
+    let path = parts
+        .iter()
+        .map(|part| text(Some(part)).ok_or_else(|| wrong("file path part is not a string")))
+        .collect::<Result<Vec<String>, Error>>()?;
+    if path.is_empty() || !path.iter().all(|part| safe_component(part)) {
+        return Err(wrong("bad file path"));
+    }
+    Ok(path)
+}
+
+// v2 "file tree": nested dictionaries whose leaves have an empty key holding the length
+fn file_tree(
+    tree: &Value,
+    prefix: &mut Vec<String>,
+    files: &mut Vec<MetainfoFile>,
+) -> Result<(), Error> {
+    let dict = tree
+        .as_dict()
+        .ok_or_else(|| wrong("file tree is not a dictionary"))?;
+    for (name, node) in dict {
+        if name.is_empty() {
+            if prefix.is_empty() {
+                return Err(wrong("bad file path"));
+            }
+            let length = node
+                .get("length")
+                .and_then(|length| length.as_integer())
+                .ok_or_else(|| wrong("file tree leaf without length"))?;
+            files.push(MetainfoFile {
+                path: prefix.clone(),
+                length,
+                padding: false,
+            });
+            continue;
+        }
+        let name = String::from_utf8_lossy(name).into_owned();
+        if !safe_component(&name) {
+            return Err(wrong("bad file path"));
+        }
+        prefix.push(name);
+        file_tree(node, prefix, files)?;
+        prefix.pop();
+    }
+    Ok(())
+}
+
+fn tracker_tiers(root: &Value) -> Vec<Vec<String>> {
+    let mut trackers: Vec<Vec<String>> = match root.get("announce-list") {
+        Some(Value::List(tiers)) => tiers
+            .iter()
+            .map(|tier| string_list(Some(tier)))
+            .filter(|tier| !tier.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    };
+    if trackers.is_empty() {
+        if let Some(announce) = text(root.get("announce")).filter(|url| !url.is_empty()) {
+            trackers.push(vec![announce]);
+        }
+    }
+    trackers
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::List(list)) => list.iter().filter_map(|item| text(Some(item))).collect(),
+        Some(item) => text(Some(item)).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+pub fn info_hash(data: &[u8]) -> Result<Id, Error> {
+    Ok(Metainfo::from_bytes(data)?.id())
+}
+
+impl Metainfo {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let root = bencode::decode(data)?;
+        if root.as_dict().is_none() {
+            return Err(wrong("top level is not a dictionary"));
+        }
+        let info = root.get("info").ok_or_else(|| wrong("missing info"))?;
+        if info.as_dict().is_none() {
+            return Err(wrong("info is not a dictionary"));
+        }
+        let info_bytes = bencode::raw_dict_value(data, "info")?
+            .ok_or_else(|| wrong("missing info"))?
+            .to_vec();
+
+        let name = utf8_text(info, "name").ok_or_else(|| wrong("missing name"))?;
+        if !safe_component(&name) {
+            return Err(wrong("bad name"));
+        }
+        let piece_length = info
+            .get("piece length")
+            .and_then(|value| value.as_integer())
+            .ok_or_else(|| wrong("missing piece length"))?;
+        if piece_length <= 0 {
+            return Err(wrong("piece length is not positive"));
+        }
+        let meta_version = info
+            .get("meta version")
+            .and_then(|value| value.as_integer());
+
+        let mut files = Vec::new();
+        let multi_file;
+        if let Some(length) = info.get("length") {
+            let length = length
+                .as_integer()
+                .ok_or_else(|| wrong("length is not an integer"))?;
+            files.push(MetainfoFile {
+                path: vec![name.clone()],
+                length,
+                padding: false,
+            });
+            multi_file = false;
+        } else if let Some(list) = info.get("files") {
+            let list = list.as_list().ok_or_else(|| wrong("files is not a list"))?;
+            for file in list {
+                let length = file
+                    .get("length")
+                    .and_then(|length| length.as_integer())
+                    .ok_or_else(|| wrong("file without length"))?;
+                let path = match file.get("path.utf-8") {
+                    Some(path) => path_list(path)?,
+                    None => path_list(file.get("path").ok_or_else(|| wrong("file without path"))?)?,
+                };
+                let padding = file
+                    .get("attr")
+                    .and_then(|attr| attr.as_bytes())
+                    .is_some_and(|attr| attr.contains(&b'p'));
+                files.push(MetainfoFile {
+                    path,
+                    length,
+                    padding,
+                });
+            }
+            multi_file = true;
+        } else if let Some(tree) = info.get("file tree") {
+            file_tree(tree, &mut Vec::new(), &mut files)?;
+            multi_file = !(files.len() == 1 && files[0].path == [name.clone()]);
+        } else {
+            return Err(wrong("missing length, files and file tree"));
+        }
+        if files.iter().any(|file| file.length < 0) {
+            return Err(wrong("negative file length"));
+        }
+
+        let pieces = info
+            .get("pieces")
+            .and_then(|pieces| pieces.as_bytes())
+            .map(|pieces| pieces.to_vec())
+            .unwrap_or_default();
+        let total = files
+            .iter()
+            .try_fold(0i64, |total, file| total.checked_add(file.length))
+            .ok_or_else(|| wrong("total size overflows"))?;
+        // Hybrid torrents carry v1 pieces next to the v2 tree and have to match it too
+        if meta_version != Some(2) || !pieces.is_empty() {
+            if pieces.len() % 20 != 0 {
+                return Err(wrong("pieces length is not a multiple of 20"));
+            }
+            let expected = (total as u64).div_ceil(piece_length as u64);
+            if pieces.len() as u64 / 20 != expected {
+                return Err(wrong("piece count does not match the total size"));
+            }
+        }
+
+        let trackers = tracker_tiers(&root);
+
+        Ok(Metainfo {
+            name,
+            files,
+            multi_file,
+            piece_length,
+            pieces,
+            trackers,
+            private: info.get("private").and_then(|value| value.as_integer()) == Some(1),
+            creation_date: root
+                .get("creation date")
+                .and_then(|value| value.as_integer()),
+            comment: utf8_text(&root, "comment"),
+            created_by: utf8_text(&root, "created by"),
+            web_seeds: string_list(root.get("url-list")),
+            source: text(info.get("source")),
+            meta_version,
+            info_bytes,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Metainfo::from_bytes(&std::fs::read(path)?)
+    }
+
+    // The info dictionary exactly as it was encoded, the input of the info hash
+    pub fn info_bytes(&self) -> &[u8] {
+        &self.info_bytes
+    }
+
+    // v2-only torrents carry no v1 piece hashes and have no v1 info hash
+    pub fn has_v1(&self) -> bool {
+        self.meta_version != Some(2) || !self.pieces.is_empty()
+    }
+
+    pub fn has_v2(&self) -> bool {
+        self.meta_version == Some(2)
+    }
+
+    pub fn info_hash_v1(&self) -> Option<String> {
+        self.has_v1()
+            .then(|| to_hex(&Sha1::digest(&self.info_bytes)))
+    }
+
+    pub fn info_hash_v2(&self) -> Option<String> {
+        self.has_v2()
+            .then(|| to_hex(&Sha256::digest(&self.info_bytes)))
+    }
+
+    // The hash the daemon reports as hashString: v1 when there is one, v2 otherwise
+    pub fn info_hash(&self) -> String {
+        self.info_hash_v1()
+            .or_else(|| self.info_hash_v2())
+            .unwrap_or_default()
+    }
+
+    pub fn id(&self) -> Id {
+        Id::Hash(self.info_hash())
+    }
+
+    pub fn total_size(&self) -> i64 {
+        self.files
+            .iter()
+            .filter(|file| !file.padding)
+            .fold(0i64, |total, file| total.saturating_add(file.length))
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len() / 20
+    }
+
+    pub fn piece_hash(&self, index: usize) -> Option<&[u8]> {
+        self.pieces.get(index * 20..index * 20 + 20)
+    }
+
+    pub fn announces(&self) -> impl Iterator<Item = &String> {
+        self.trackers.iter().flatten()
+    }
+
+    // Relative paths on disk, the top directory of multi-file torrents included
+    pub fn file_paths(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|file| !file.padding)
+            .map(|file| {
+                if self.multi_file {
+                    format!("{}/{}", self.name, file.path_string())
+                } else {
+                    file.path_string()
+                }
+            })
+            .collect()
+    }
+}
+
+// Edits a .torrent in place: keys it does not know about are kept as they are, and the info
+// dictionary keeps its original bytes until an edit touches it
+#[derive(Debug, Clone)]
+pub struct MetainfoEditor {
+    root: BTreeMap<Vec<u8>, Value>,
+    info: BTreeMap<Vec<u8>, Value>,
+    info_bytes: Vec<u8>,
+    info_modified: bool,
+    original_hash: String,
+}
+
+fn string_values(urls: &[String]) -> Value {
+    Value::List(urls.iter().map(|url| Value::from(url.as_str())).collect())
+}
+
+impl MetainfoEditor {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let metainfo = Metainfo::from_bytes(data)?;
+        let mut root = match bencode::decode(data)? {
+            Value::Dict(root) => root,
+            _ => return Err(wrong("top level is not a dictionary")),
+        };
+        let info = match root.remove(b"info".as_slice()) {
+            Some(Value::Dict(info)) => info,
+            _ => return Err(wrong("info is not a dictionary")),
+        };
+        Ok(MetainfoEditor {
+            root,
+            info,
+            original_hash: metainfo.info_hash(),
+            info_bytes: metainfo.info_bytes,
+            info_modified: false,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        MetainfoEditor::from_bytes(&std::fs::read(path)?)
+    }
+
+    fn set_root(&mut self, key: &str, value: Option<Value>) {
+        match value {
+            Some(value) => self.root.insert(key.as_bytes().to_vec(), value),
+            None => self.root.remove(key.as_bytes()),
+        };
+    }
+
+    fn set_info(&mut self, key: &str, value: Option<Value>) {
+        let old = match value {
+            Some(value) => self.info.insert(key.as_bytes().to_vec(), value),
+            None => self.info.remove(key.as_bytes()),
+        };
+        if old.as_ref() == self.info.get(key.as_bytes()) {
+            return;
+        }
+        self.info_modified = true;
+        if self.info_hash_changed() {
+            log::warn!(
+                "editing {} changes the info hash of {}, the daemon will see a different torrent",
+                key,
+                self.original_hash
+            );
+        }
+    }
+
+    pub fn trackers(&self) -> Vec<Vec<String>> {
+        tracker_tiers(&Value::Dict(self.root.clone()))
+    }
+
+    // Writes announce with the first url and announce-list when there is more than one url
+    pub fn set_trackers(&mut self, tiers: Vec<Vec<String>>) -> &mut Self {
+        let tiers: Vec<Vec<String>> = tiers.into_iter().filter(|tier| !tier.is_empty()).collect();
+        let announce = tiers
+            .first()
+            .and_then(|tier| tier.first())
+            .map(|url| Value::from(url.as_str()));
+        self.set_root("announce", announce);
+        let announce_list = (tiers.iter().flatten().count() > 1)
+            .then(|| Value::List(tiers.iter().map(|tier| string_values(tier)).collect()));
+        self.set_root("announce-list", announce_list);
+        self
+    }
+
+    pub fn clear_trackers(&mut self) -> &mut Self {
+        self.set_trackers(Vec::new())
+    }
+
+    // Appends the url as a new tier unless it is already there
+    pub fn add_tracker(&mut self, url: &str) -> &mut Self {
+        let mut tiers = self.trackers();
+        if !tiers.iter().flatten().any(|known| known == url) {
+            tiers.push(vec![url.to_string()]);
+        }
+        self.set_trackers(tiers)
+    }
+
+    pub fn remove_tracker(&mut self, url: &str) -> &mut Self {
+        let mut tiers = self.trackers();
+        for tier in tiers.iter_mut() {
+            tier.retain(|known| known != url);
+        }
+        self.set_trackers(tiers)
+    }
+
+    pub fn replace_tracker(&mut self, old: &str, new: &str) -> &mut Self {
+        let mut tiers = self.trackers();
+        for url in tiers.iter_mut().flatten() {
+            if url == old {
+                *url = new.to_string();
+            }
+        }
+        self.set_trackers(tiers)
+    }
+
+    pub fn set_web_seeds(&mut self, urls: Vec<String>) -> &mut Self {
+        let urls = (!urls.is_empty()).then(|| string_values(&urls));
+        self.set_root("url-list", urls);
+        self
+    }
+
+    pub fn set_comment(&mut self, comment: Option<&str>) -> &mut Self {
+        self.set_root("comment", comment.map(Value::from));
+        self.root.remove(b"comment.utf-8".as_slice());
+        self
+    }
+
+    pub fn set_created_by(&mut self, created_by: Option<&str>) -> &mut Self {
+        self.set_root("created by", created_by.map(Value::from));
+        self
+    }
+
+    pub fn set_creation_date(&mut self, creation_date: Option<i64>) -> &mut Self {
+        self.set_root("creation date", creation_date.map(Value::from));
+        self
+    }
+
+    // Changes the info hash
+    pub fn set_private(&mut self, private: bool) -> &mut Self {
+        self.set_info("private", private.then(|| Value::from(1)));
+        self
+    }
+
+    // Changes the info hash
+    pub fn set_source(&mut self, source: Option<&str>) -> &mut Self {
+        self.set_info("source", source.map(Value::from));
+        self
+    }
+
+    pub fn original_info_hash(&self) -> &str {
+        &self.original_hash
+    }
+
+    pub fn info_hash(&self) -> String {
+        self.metainfo()
+            .map(|metainfo| metainfo.info_hash())
+            .unwrap_or_default()
+    }
+
+    pub fn info_hash_changed(&self) -> bool {
+        self.info_modified && self.info_hash() != self.original_hash
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![b'd'];
+        let mut info_written = false;
+        let write_info = |out: &mut Vec<u8>| {
+            out.extend_from_slice(b"4:info");
+            if self.info_modified {
+                Value::Dict(self.info.clone()).encode_to(out);
+            } else {
+                out.extend_from_slice(&self.info_bytes);
+            }
+        };
+        for (key, value) in &self.root {
+            if !info_written && key.as_slice() > b"info".as_slice() {
+                write_info(&mut out);
+                info_written = true;
+            }
+            out.extend_from_slice(format!("{}:", key.len()).as_bytes());
+            out.extend_from_slice(key);
+            value.encode_to(&mut out);
+        }
+        if !info_written {
+            write_info(&mut out);
+        }
+        out.push(b'e');
+        out
+    }
+
+    pub fn metainfo(&self) -> Result<Metainfo, Error> {
+        Metainfo::from_bytes(&self.to_bytes())
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn add_args(&self) -> TorrentAddArgs {
+        TorrentAddArgs {
+            metainfo: Some(STANDARD.encode(self.to_bytes())),
+            ..TorrentAddArgs::default()
+        }
+    }
+}
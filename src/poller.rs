@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::cache::merge_torrent;
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids};
+use crate::torrent::{Torrent, TorrentFields, TorrentGetArgs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldRate {
+    Static,
+    Slow,
+    Fast,
+}
+
+impl TorrentFields {
+    // Static fields come from the metainfo, slow ones change on user action, fast ones with
+    // transfer. Files carries bytesCompleted next to the names, so it is fast.
+    pub fn rate(self) -> FieldRate {
+        match self {
+            TorrentFields::AddedDate
+            | TorrentFields::Comment
+            | TorrentFields::Creator
+            | TorrentFields::DateCreated
+            | TorrentFields::FileCount
+            | TorrentFields::HashString
+            | TorrentFields::Id
+            | TorrentFields::IsPrivate
+            | TorrentFields::MagnetLink
+            | TorrentFields::Name
+            | TorrentFields::PieceCount
+            | TorrentFields::PieceSize
+            | TorrentFields::PrimaryMimeType
+            | TorrentFields::TorrentFile
+            | TorrentFields::TotalSize
+            | TorrentFields::Webseeds => FieldRate::Static,
+            TorrentFields::BandwidthPriority
+            | TorrentFields::DoneDate
+            | TorrentFields::DownloadDir
+            | TorrentFields::DownloadLimit
+            | TorrentFields::DownloadLimited
+            | TorrentFields::EditDate
+            | TorrentFields::HonorsSessionLimits
+            | TorrentFields::Labels
+            | TorrentFields::ManualAnnounceTime
+            | TorrentFields::MaxConnectedPeers
+            | TorrentFields::PeerLimit
+            | TorrentFields::Priorities
+            | TorrentFields::QueuePosition
+            | TorrentFields::SeedIdleLimit
+            | TorrentFields::SeedIdleMode
+            | TorrentFields::SeedRatioLimit
+            | TorrentFields::SeedRatioMode
+            | TorrentFields::SizeWhenDone
+            | TorrentFields::StartDate
+            | TorrentFields::Trackers
+            | TorrentFields::TrackerList
+            | TorrentFields::TrackerStats
+            | TorrentFields::UploadLimit
+            | TorrentFields::UploadLimited
+            | TorrentFields::Wanted => FieldRate::Slow,
+            _ => FieldRate::Fast,
+        }
+    }
+}
+
+pub fn fields_with_rate(fields: &[TorrentFields], rate: FieldRate) -> Vec<TorrentFields> {
+    fields
+        .iter()
+        .copied()
+        .filter(|field| field.rate() == rate)
+        .collect()
+}
+
+fn push_unique(fields: &mut Vec<TorrentFields>, extra: &[TorrentFields]) {
+    for field in extra {
+        if !fields.contains(field) {
+            fields.push(*field);
+        }
+    }
+}
+
+// Fetches static fields once per torrent, slow fields every slow_every polls and fast fields
+// on every poll, merging the answers into complete torrents. Magnets keep getting their
+// static fields until the metadata is complete.
+#[derive(Debug)]
+pub struct AdaptivePoller {
+    ids: Option<Ids>,
+    static_fields: Vec<TorrentFields>,
+    slow_fields: Vec<TorrentFields>,
+    fast_fields: Vec<TorrentFields>,
+    slow_every: u64,
+    polls: u64,
+    torrents: BTreeMap<i64, Torrent>,
+}
+
+impl AdaptivePoller {
+    pub fn new(fields: &[TorrentFields]) -> Self {
+        let mut fast_fields = vec![TorrentFields::Id, TorrentFields::MetadataPercentComplete];
+        push_unique(&mut fast_fields, &fields_with_rate(fields, FieldRate::Fast));
+        AdaptivePoller {
+            ids: None,
+            static_fields: fields_with_rate(fields, FieldRate::Static),
+            slow_fields: fields_with_rate(fields, FieldRate::Slow),
+            fast_fields,
+            slow_every: 10,
+            polls: 0,
+            torrents: BTreeMap::new(),
+        }
+    }
+
+    pub fn ids(mut self, ids: Ids) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    pub fn slow_every(mut self, polls: u64) -> Self {
+        self.slow_every = polls.max(1);
+        self
+    }
+
+    pub fn torrents(&self) -> impl Iterator<Item = &Torrent> {
+        self.torrents.values()
+    }
+
+    pub fn get(&self, id: i64) -> Option<&Torrent> {
+        self.torrents.get(&id)
+    }
+
+    // slow_every is never 0
+    pub fn fields_for_poll(&self, poll: u64) -> Vec<TorrentFields> {
+        let mut fields = self.fast_fields.clone();
+        if poll == 0 {
+            push_unique(&mut fields, &self.static_fields);
+        }
+        if poll % self.slow_every == 0 {
+            push_unique(&mut fields, &self.slow_fields);
+        }
+        fields
+    }
+
+    pub async fn poll(&mut self, client: &mut Client) -> Result<Vec<Torrent>, Error> {
+        let fields = self.fields_for_poll(self.polls);
+        let first = self.polls == 0;
+        self.polls += 1;
+        let response = client
+            .torrent_get(TorrentGetArgs {
+                ids: self.ids.clone(),
+                fields,
+            })
+            .await?;
+        let mut seen = HashSet::new();
+        let mut missing = Vec::new();
+        for torrent in response.torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            seen.insert(id);
+            match self.torrents.get_mut(&id) {
+                Some(current) => {
+                    let had_metadata = current
+                        .metadata_percent_complete
+                        .is_some_and(|percent| percent >= 1.0);
+                    merge_torrent(current, torrent)?;
+                    if !first && !had_metadata {
+                        missing.push(Id::Id(id));
+                    }
+                }
+                None => {
+                    if !first {
+                        missing.push(Id::Id(id));
+                    }
+                    self.torrents.insert(id, torrent);
+                }
+            }
+        }
+        self.torrents.retain(|id, _| seen.contains(id));
+        let has_fields = !(self.static_fields.is_empty() && self.slow_fields.is_empty());
+        if !missing.is_empty() && has_fields {
+            let mut fields = vec![TorrentFields::Id];
+            push_unique(&mut fields, &self.static_fields);
+            push_unique(&mut fields, &self.slow_fields);
+            let response = client
+                .torrent_get(TorrentGetArgs {
+                    ids: Some(Ids::Array(missing)),
+                    fields,
+                })
+                .await?;
+            for torrent in response.torrents {
+                if let Some(current) = torrent.id.and_then(|id| self.torrents.get_mut(&id)) {
+                    merge_torrent(current, torrent)?;
+                }
+            }
+        }
+        Ok(self.torrents.values().cloned().collect())
+    }
+}
@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use regex::Regex;
+use serde_json::Value;
+use url::Url;
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::Ids;
+use crate::torrent::{Torrent, TorrentFields, TorrentGetArgs, TorrentStatus};
+use crate::util::now;
+
+// Host part of an announce url, without scheme, credentials, port and path
+pub fn announce_host(announce: &str) -> Option<String> {
+    let url = Url::parse(announce).ok()?;
+    url.host_str()
+        .filter(|host| !host.is_empty())
+        .map(|host| host.to_ascii_lowercase())
+}
+
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    Status(Vec<TorrentStatus>),
+    Label(String),
+    TrackerHost(String),
+    MinRatio(f64),
+    MaxRatio(f64),
+    MinSize(i64),
+    MaxSize(i64),
+    AddedAfter(i64),
+    AddedBefore(i64),
+    Name(Regex),
+}
+
+impl Predicate {
+    pub fn fields(&self) -> &'static [TorrentFields] {
+        match self {
+            Predicate::Status(_) => &[TorrentFields::Status],
+            Predicate::Label(_) => &[TorrentFields::Labels],
+            Predicate::TrackerHost(_) => &[TorrentFields::Trackers],
+            Predicate::MinRatio(_) | Predicate::MaxRatio(_) => &[TorrentFields::UploadRatio],
+            Predicate::MinSize(_) | Predicate::MaxSize(_) => &[TorrentFields::TotalSize],
+            Predicate::AddedAfter(_) | Predicate::AddedBefore(_) => &[TorrentFields::AddedDate],
+            Predicate::Name(_) => &[TorrentFields::Name],
+        }
+    }
+
+    pub fn matches(&self, torrent: &Torrent) -> bool {
+        match self {
+            Predicate::Status(statuses) => torrent
+                .status
+                .and_then(|status| TorrentStatus::try_from(status).ok())
+                .is_some_and(|status| statuses.contains(&status)),
+            Predicate::Label(label) => torrent
+                .labels
+                .as_ref()
+                .is_some_and(|labels| labels.contains(label)),
+            Predicate::TrackerHost(host) => torrent.trackers.as_ref().is_some_and(|trackers| {
+                trackers.iter().any(|tracker| {
+                    announce_host(&tracker.announce).is_some_and(|current| {
+                        current == *host || current.ends_with(&format!(".{}", host))
+                    })
+                })
+            }),
+            Predicate::MinRatio(ratio) => torrent.upload_ratio.is_some_and(|value| value >= *ratio),
+            Predicate::MaxRatio(ratio) => torrent.upload_ratio.is_some_and(|value| value <= *ratio),
+            Predicate::MinSize(size) => torrent.total_size.is_some_and(|value| value >= *size),
+            Predicate::MaxSize(size) => torrent.total_size.is_some_and(|value| value <= *size),
+            Predicate::AddedAfter(date) => torrent.added_date.is_some_and(|value| value >= *date),
+            Predicate::AddedBefore(date) => torrent.added_date.is_some_and(|value| value < *date),
+            Predicate::Name(regex) => torrent
+                .name
+                .as_ref()
+                .is_some_and(|name| regex.is_match(name)),
+        }
+    }
+}
+
+// Field name as the daemon and the serialized Torrent spell it
+fn field_name(field: TorrentFields) -> String {
+    match serde_json::to_value(field) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+// Missing values sort last in both directions, numbers compare as numbers, strings ignore case
+fn compare_values(a: Option<&Value>, b: Option<&Value>, desc: bool) -> Ordering {
+    let a = a.filter(|value| !value.is_null());
+    let b = b.filter(|value| !value.is_null());
+    let ordering = match (a, b) {
+        (None, None) => return Ordering::Equal,
+        (None, Some(_)) => return Ordering::Greater,
+        (Some(_), None) => return Ordering::Less,
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Array(a)), Some(Value::Array(b))) => a.len().cmp(&b.len()),
+        _ => Ordering::Equal,
+    };
+    if desc {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TorrentQuery {
+    ids: Option<Ids>,
+    predicates: Vec<Predicate>,
+    fields: Vec<TorrentFields>,
+    sort: Vec<(TorrentFields, bool)>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl TorrentQuery {
+    pub fn new() -> Self {
+        TorrentQuery::default()
+    }
+
+    pub fn ids(mut self, ids: Ids) -> Self {
+        self.ids = Some(ids);
+        self
+    }
+
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    pub fn status(self, statuses: &[TorrentStatus]) -> Self {
+        self.filter(Predicate::Status(statuses.to_vec()))
+    }
+
+    pub fn label(self, label: &str) -> Self {
+        self.filter(Predicate::Label(label.to_string()))
+    }
+
+    pub fn tracker_host(self, host: &str) -> Self {
+        self.filter(Predicate::TrackerHost(host.to_ascii_lowercase()))
+    }
+
+    pub fn min_ratio(self, ratio: f64) -> Self {
+        self.filter(Predicate::MinRatio(ratio))
+    }
+
+    pub fn max_ratio(self, ratio: f64) -> Self {
+        self.filter(Predicate::MaxRatio(ratio))
+    }
+
+    pub fn min_size(self, bytes: i64) -> Self {
+        self.filter(Predicate::MinSize(bytes))
+    }
+
+    pub fn max_size(self, bytes: i64) -> Self {
+        self.filter(Predicate::MaxSize(bytes))
+    }
+
+    pub fn added_after(self, timestamp: i64) -> Self {
+        self.filter(Predicate::AddedAfter(timestamp))
+    }
+
+    pub fn added_before(self, timestamp: i64) -> Self {
+        self.filter(Predicate::AddedBefore(timestamp))
+    }
+
+    pub fn older_than(self, age: Duration) -> Self {
+        self.added_before(now() - age.as_secs() as i64)
+    }
+
+    pub fn newer_than(self, age: Duration) -> Self {
+        self.added_after(now() - age.as_secs() as i64)
+    }
+
+    pub fn name_regex(self, pattern: &str) -> Result<Self, Error> {
+        Ok(self.filter(Predicate::Name(Regex::new(pattern)?)))
+    }
+
+    // Extra fields to return besides the ones the predicates and sorting need
+    pub fn fields(mut self, fields: &[TorrentFields]) -> Self {
+        self.fields.extend_from_slice(fields);
+        self
+    }
+
+    pub fn sort_by(mut self, field: TorrentFields) -> Self {
+        self.sort.push((field, false));
+        self
+    }
+
+    pub fn sort_by_desc(mut self, field: TorrentFields) -> Self {
+        self.sort.push((field, true));
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn required_fields(&self) -> Vec<TorrentFields> {
+        let mut fields = vec![TorrentFields::Id];
+        let needed = self
+            .fields
+            .iter()
+            .chain(
+                self.predicates
+                    .iter()
+                    .flat_map(|predicate| predicate.fields()),
+            )
+            .chain(self.sort.iter().map(|(field, _)| field));
+        for field in needed {
+            if !fields.contains(field) {
+                fields.push(*field);
+            }
+        }
+        fields
+    }
+
+    pub fn matches(&self, torrent: &Torrent) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(torrent))
+    }
+
+    pub fn apply(&self, torrents: Vec<Torrent>) -> Vec<Torrent> {
+        let mut found: Vec<Torrent> = torrents
+            .into_iter()
+            .filter(|torrent| self.matches(torrent))
+            .collect();
+        if !self.sort.is_empty() {
+            let keys: Vec<(String, bool)> = self
+                .sort
+                .iter()
+                .map(|(field, desc)| (field_name(*field), *desc))
+                .collect();
+            let mut rows: Vec<(Value, Torrent)> = found
+                .into_iter()
+                .map(|torrent| (serde_json::to_value(&torrent).unwrap_or_default(), torrent))
+                .collect();
+            rows.sort_by(|(a, _), (b, _)| {
+                keys.iter()
+                    .map(|(key, desc)| compare_values(a.get(key), b.get(key), *desc))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+            found = rows.into_iter().map(|(_, torrent)| torrent).collect();
+        }
+        found
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+impl Client {
+    pub async fn query(&mut self, query: &TorrentQuery) -> Result<Vec<Torrent>, Error> {
+        let torrents = self
+            .torrent_get(TorrentGetArgs {
+                ids: query.ids.clone(),
+                fields: query.required_fields(),
+            })
+            .await?
+            .torrents;
+        Ok(query.apply(torrents))
+    }
+}
@@ -1,4 +1,5 @@
 use std::convert::From;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::{json, Value};
@@ -19,6 +20,7 @@ pub struct RpcRequest {
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all(serialize = "kebab-case"))]
 pub enum Method {
+    SessionSet,
     SessionGet,
     SessionStats,
     BlocklistUpdate,
@@ -42,7 +44,7 @@ pub enum Method {
     QueueMoveBottom,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Id {
     Id(i64),
@@ -67,7 +69,7 @@ impl From<String> for Id {
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
 pub enum Ids {
     Id(i64),
     Array(Vec<Id>),
@@ -75,12 +77,26 @@ pub enum Ids {
     RecentlyActive,
 }
 
+// The daemon answers recently-active with the torrents changed or removed in the last 60
+// seconds. Polls further apart than this have to load everything, the rest of the minute is
+// left for slow requests.
+pub(crate) const RECENTLY_ACTIVE_WINDOW: Duration = Duration::from_secs(50);
+
 impl From<i64> for Ids {
     fn from(id: i64) -> Self {
         Ids::Id(id)
     }
 }
 
+impl From<Id> for Ids {
+    fn from(id: Id) -> Self {
+        match id {
+            Id::Id(id) => Ids::Id(id),
+            Id::Hash(hash) => Ids::Array(vec![Id::Hash(hash)]),
+        }
+    }
+}
+
 impl From<Vec<Id>> for Ids {
     fn from(values: Vec<Id>) -> Self {
         let mut arr = Vec::new();
@@ -147,7 +163,7 @@ impl Client {
     }
 
     pub async fn queue_move_top(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::QueueMoveTop,
             arguments: value,
@@ -159,7 +175,7 @@ impl Client {
     }
 
     pub async fn queue_move_up(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::QueueMoveUp,
             arguments: value,
@@ -171,7 +187,7 @@ impl Client {
     }
 
     pub async fn queue_move_down(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::QueueMoveDown,
             arguments: value,
@@ -183,7 +199,7 @@ impl Client {
     }
 
     pub async fn queue_move_bottom(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::QueueMoveBottom,
             arguments: value,
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids};
+use crate::torrent::{TorrentFields, TorrentGetArgs, TorrentSetArgs};
+use crate::tracker::{TrackerList, TrackerReplace, TRACKER_LIST_RPC_VERSION};
+
+#[derive(Debug, Clone)]
+pub enum AnnounceRewrite {
+    Prefix { from: String, to: String },
+    Regex { pattern: Regex, replacement: String },
+}
+
+impl AnnounceRewrite {
+    pub fn prefix(from: &str, to: &str) -> Self {
+        AnnounceRewrite::Prefix {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    // The replacement may use $1 or ${name} to refer to capture groups
+    pub fn regex(pattern: &str, replacement: &str) -> Result<Self, Error> {
+        Ok(AnnounceRewrite::Regex {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.to_string(),
+        })
+    }
+
+    pub fn apply(&self, announce: &str) -> Option<String> {
+        let rewritten = match self {
+            AnnounceRewrite::Prefix { from, to } => {
+                let rest = announce.strip_prefix(from.as_str())?;
+                format!("{}{}", to, rest)
+            }
+            AnnounceRewrite::Regex {
+                pattern,
+                replacement,
+            } => {
+                if !pattern.is_match(announce) {
+                    return None;
+                }
+                pattern
+                    .replace_all(announce, replacement.as_str())
+                    .into_owned()
+            }
+        };
+        if rewritten == announce {
+            None
+        } else {
+            Some(rewritten)
+        }
+    }
+}
+
+// The first rule that changes an url wins
+pub fn rewrite_announce(rules: &[AnnounceRewrite], announce: &str) -> Option<String> {
+    rules.iter().find_map(|rule| rule.apply(announce))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnounceChange {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RewrittenTorrent {
+    pub id: i64,
+    pub name: Option<String>,
+    pub hash_string: Option<String>,
+    pub changes: Vec<AnnounceChange>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RewriteSummary {
+    pub dry_run: bool,
+    pub scanned: usize,
+    pub torrents: Vec<RewrittenTorrent>,
+}
+
+impl RewriteSummary {
+    pub fn changed(&self) -> usize {
+        self.torrents
+            .iter()
+            .filter(|torrent| torrent.error.is_none())
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.torrents.len() - self.changed()
+    }
+}
+
+impl fmt::Display for RewriteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for torrent in &self.torrents {
+            let name = torrent.name.as_deref().unwrap_or("");
+            match &torrent.error {
+                Some(error) => writeln!(f, "#{} {}: failed: {}", torrent.id, name, error)?,
+                None => writeln!(f, "#{} {}", torrent.id, name)?,
+            }
+            for change in &torrent.changes {
+                writeln!(f, "    {} -> {}", change.from, change.to)?;
+            }
+        }
+        let verb = if self.dry_run {
+            "would change"
+        } else {
+            "changed"
+        };
+        write!(
+            f,
+            "scanned {} torrents, {} {}, {} failed",
+            self.scanned,
+            verb,
+            self.changed(),
+            self.failed()
+        )
+    }
+}
+
+impl Client {
+    pub async fn rewrite_announce_urls(
+        &mut self,
+        ids: Option<Ids>,
+        rules: &[AnnounceRewrite],
+        dry_run: bool,
+    ) -> Result<RewriteSummary, Error> {
+        let has_tracker_list = self.rpc_version().await? >= TRACKER_LIST_RPC_VERSION;
+        let torrents = self
+            .torrent_get(TorrentGetArgs {
+                ids,
+                fields: vec![
+                    TorrentFields::Id,
+                    TorrentFields::Name,
+                    TorrentFields::HashString,
+                    TorrentFields::Trackers,
+                ],
+            })
+            .await?
+            .torrents;
+        let mut summary = RewriteSummary {
+            dry_run,
+            scanned: torrents.len(),
+            torrents: Vec::new(),
+        };
+        for torrent in torrents {
+            let (Some(id), Some(trackers)) = (torrent.id, torrent.trackers) else {
+                continue;
+            };
+            let mut trackers = trackers;
+            trackers.sort_by_key(|tracker| (tracker.tier, tracker.id));
+            // Every target comes from the original urls, so chains and swaps are not applied twice
+            let targets: Vec<String> = trackers
+                .iter()
+                .map(|tracker| {
+                    rewrite_announce(rules, &tracker.announce)
+                        .unwrap_or_else(|| tracker.announce.clone())
+                })
+                .collect();
+            let changes: Vec<AnnounceChange> = trackers
+                .iter()
+                .zip(&targets)
+                .filter(|(tracker, to)| tracker.announce != **to)
+                .map(|(tracker, to)| AnnounceChange {
+                    from: tracker.announce.clone(),
+                    to: to.clone(),
+                })
+                .collect();
+            if changes.is_empty() {
+                continue;
+            }
+            // Two urls can collapse into one, the daemon refuses duplicates so the later ones go
+            let mut list = TrackerList::default();
+            let mut tier = None;
+            let mut replace = Vec::new();
+            let mut remove = Vec::new();
+            for (tracker, to) in trackers.iter().zip(&targets) {
+                if list.contains(to) {
+                    remove.push(Id::Id(tracker.id));
+                    continue;
+                }
+                if tier != Some(tracker.tier) {
+                    list.tiers.push(Vec::new());
+                    tier = Some(tracker.tier);
+                }
+                list.add(to, Some(list.tiers.len() - 1));
+                if tracker.announce != *to {
+                    replace.push((tracker.id, to.clone()));
+                }
+            }
+            let mut rewritten = RewrittenTorrent {
+                id,
+                name: torrent.name,
+                hash_string: torrent.hash_string,
+                changes,
+                error: None,
+            };
+            if !dry_run {
+                let mut args = TorrentSetArgs {
+                    ids: Ids::Id(id),
+                    ..TorrentSetArgs::default()
+                };
+                if has_tracker_list {
+                    args.tracker_list = Some(list.to_string());
+                } else {
+                    if !remove.is_empty() {
+                        args.tracker_remove = Some(Ids::Array(remove));
+                    }
+                    if !replace.is_empty() {
+                        args.tracker_replace = Some(TrackerReplace(replace));
+                    }
+                }
+                if let Err(err) = self.torrent_set(args).await {
+                    rewritten.error = Some(err.to_string());
+                }
+            }
+            summary.torrents.push(rewritten);
+        }
+        Ok(summary)
+    }
+}
@@ -0,0 +1,289 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::session::{Session, SessionFields, SessionGetArgs};
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+const MINUTES_PER_WEEK: i64 = 7 * MINUTES_PER_DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weekday {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Sunday,
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+    ];
+
+    // Days are numbered from Sunday, the same as tr_sched_day
+    pub fn number(self) -> i64 {
+        self as i64
+    }
+
+    pub fn from_number(number: i64) -> Weekday {
+        Weekday::ALL[number.rem_euclid(7) as usize]
+    }
+
+    pub fn bit(self) -> i64 {
+        1 << self.number()
+    }
+
+    pub fn previous(self) -> Weekday {
+        Weekday::from_number(self.number() - 1)
+    }
+
+    pub fn next(self) -> Weekday {
+        Weekday::from_number(self.number() + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Weekdays(i64);
+
+impl Weekdays {
+    pub const NONE: Weekdays = Weekdays(0);
+    pub const WEEKDAYS: Weekdays = Weekdays(62);
+    pub const WEEKEND: Weekdays = Weekdays(65);
+    pub const ALL: Weekdays = Weekdays(127);
+
+    pub fn from_bits(bits: i64) -> Result<Self, Error> {
+        if bits & !Weekdays::ALL.0 != 0 {
+            return Err(Error::WrongScheduleDays(bits));
+        }
+        Ok(Weekdays(bits))
+    }
+
+    pub fn bits(self) -> i64 {
+        self.0
+    }
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.0 & day.bit() != 0
+    }
+
+    pub fn insert(&mut self, day: Weekday) {
+        self.0 |= day.bit();
+    }
+
+    pub fn remove(&mut self, day: Weekday) {
+        self.0 &= !day.bit();
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn days(self) -> Vec<Weekday> {
+        Weekday::ALL
+            .iter()
+            .copied()
+            .filter(|day| self.contains(*day))
+            .collect()
+    }
+}
+
+impl From<Weekday> for Weekdays {
+    fn from(day: Weekday) -> Self {
+        Weekdays(day.bit())
+    }
+}
+
+impl FromIterator<Weekday> for Weekdays {
+    fn from_iter<I: IntoIterator<Item = Weekday>>(iter: I) -> Self {
+        let mut days = Weekdays::NONE;
+        for day in iter {
+            days.insert(day);
+        }
+        days
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    pub fn new(hour: u8, minute: u8) -> Result<Self, Error> {
+        if hour > 23 || minute > 59 {
+            return Err(Error::WrongScheduleTime(format!("{}:{}", hour, minute)));
+        }
+        Ok(TimeOfDay { hour, minute })
+    }
+
+    pub fn from_minutes(minutes: i64) -> Result<Self, Error> {
+        if !(0..MINUTES_PER_DAY).contains(&minutes) {
+            return Err(Error::WrongScheduleTime(minutes.to_string()));
+        }
+        Ok(TimeOfDay {
+            hour: (minutes / 60) as u8,
+            minute: (minutes % 60) as u8,
+        })
+    }
+
+    pub fn hour(self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(self) -> u8 {
+        self.minute
+    }
+
+    pub fn minutes(self) -> i64 {
+        i64::from(self.hour) * 60 + i64::from(self.minute)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let wrong = || Error::WrongScheduleTime(s.to_string());
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(wrong)?;
+        if hour.is_empty() || hour.len() > 2 || minute.len() != 2 {
+            return Err(wrong());
+        }
+        let hour = hour.parse().map_err(|_| wrong())?;
+        let minute = minute.parse().map_err(|_| wrong())?;
+        TimeOfDay::new(hour, minute)
+    }
+}
+
+impl TryFrom<&str> for TimeOfDay {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AltSpeedSchedule {
+    pub enabled: bool,
+    pub begin: TimeOfDay,
+    pub end: TimeOfDay,
+    pub days: Weekdays,
+}
+
+impl AltSpeedSchedule {
+    pub fn new(begin: TimeOfDay, end: TimeOfDay, days: Weekdays) -> Self {
+        AltSpeedSchedule {
+            enabled: true,
+            begin,
+            end,
+            days,
+        }
+    }
+
+    pub fn from_session(session: &Session) -> Result<Option<Self>, Error> {
+        match (
+            session.alt_speed_time_enabled,
+            session.alt_speed_time_begin,
+            session.alt_speed_time_end,
+            session.alt_speed_time_day,
+        ) {
+            (Some(enabled), Some(begin), Some(end), Some(days)) => Ok(Some(AltSpeedSchedule {
+                enabled,
+                begin: TimeOfDay::from_minutes(begin)?,
+                end: TimeOfDay::from_minutes(end)?,
+                days: Weekdays::from_bits(days)?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    // An end at or before the begin means the window runs past midnight into the next day
+    pub fn is_overnight(&self) -> bool {
+        self.end <= self.begin
+    }
+
+    pub fn duration_minutes(&self) -> i64 {
+        let length = self.end.minutes() - self.begin.minutes();
+        if length > 0 {
+            length
+        } else {
+            length + MINUTES_PER_DAY
+        }
+    }
+
+    pub fn is_active_at(&self, day: Weekday, time: TimeOfDay) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let now = day.number() * MINUTES_PER_DAY + time.minutes();
+        self.days.days().into_iter().any(|start_day| {
+            let begin = start_day.number() * MINUTES_PER_DAY + self.begin.minutes();
+            (now - begin).rem_euclid(MINUTES_PER_WEEK) < self.duration_minutes()
+        })
+    }
+
+    // The daemon evaluates the schedule in its local time, so the caller passes its UTC offset
+    pub fn is_active_at_timestamp(&self, timestamp: i64, utc_offset_minutes: i64) -> bool {
+        let local_minutes = timestamp.div_euclid(60) + utc_offset_minutes;
+        let days = local_minutes.div_euclid(MINUTES_PER_DAY);
+        // 1970-01-01 was a Thursday
+        let day = Weekday::from_number(days + Weekday::Thursday.number());
+        let time =
+            TimeOfDay::from_minutes(local_minutes.rem_euclid(MINUTES_PER_DAY)).unwrap_or_default();
+        self.is_active_at(day, time)
+    }
+
+    pub fn to_session(&self) -> Session {
+        Session {
+            alt_speed_time_enabled: Some(self.enabled),
+            alt_speed_time_begin: Some(self.begin.minutes()),
+            alt_speed_time_end: Some(self.end.minutes()),
+            alt_speed_time_day: Some(self.days.bits()),
+            ..Session::default()
+        }
+    }
+}
+
+impl Client {
+    pub async fn alt_speed_schedule(&mut self) -> Result<AltSpeedSchedule, Error> {
+        let session = self
+            .session_get(Some(SessionGetArgs {
+                fields: vec![
+                    SessionFields::AltSpeedTimeEnabled,
+                    SessionFields::AltSpeedTimeBegin,
+                    SessionFields::AltSpeedTimeEnd,
+                    SessionFields::AltSpeedTimeDay,
+                ],
+            }))
+            .await?;
+        AltSpeedSchedule::from_session(&session)?.ok_or(Error::NoArguments)
+    }
+
+    pub async fn set_alt_speed_schedule(
+        &mut self,
+        schedule: AltSpeedSchedule,
+    ) -> Result<(), Error> {
+        if schedule.enabled && schedule.days.is_empty() {
+            return Err(Error::WrongScheduleDays(schedule.days.bits()));
+        }
+        self.session_set(schedule.to_session()).await
+    }
+}
@@ -86,7 +86,7 @@ pub struct SessionStats {
     pub current_stats: Stats,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Units {
     pub speed_units: Vec<String>,
@@ -97,7 +97,7 @@ pub struct Units {
     pub memory_bytes: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Session {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -217,7 +217,7 @@ impl Client {
             return Err(Error::WrongSessionSetFields);
         }
         let request = RpcRequest {
-            method: Method::SessionGet,
+            method: Method::SessionSet,
             arguments: Some(json!(args)),
             tag: None,
         };
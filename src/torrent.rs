@@ -2,23 +2,29 @@ use std::convert::TryFrom;
 use std::io::Read;
 
 use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 use crate::client::Client;
 use crate::error::Error;
 use crate::request::{Ids, Method, RpcRequest};
 use crate::response::value_from_response;
+use crate::tracker::TrackerReplace;
 
 pub fn file_to_metadata(path: &str) -> Result<String, Error> {
     let mut file = std::fs::File::open(path)?;
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
-    let buf = STANDARD.encode(buffer);
-    Ok(buf)
+    bytes_to_metadata(&buffer)
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+pub fn bytes_to_metadata(data: &[u8]) -> Result<String, Error> {
+    Ok(STANDARD.encode(data))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum TorrentFields {
     ActivityDate,
@@ -86,6 +92,7 @@ pub enum TorrentFields {
     StartDate,
     Status,
     Trackers,
+    TrackerList,
     TrackerStats,
     TotalSize,
     TorrentFile,
@@ -165,6 +172,7 @@ impl TryFrom<&str> for TorrentFields {
             "startdate" => Ok(TorrentFields::StartDate),
             "status" => Ok(TorrentFields::Status),
             "trackers" => Ok(TorrentFields::Trackers),
+            "trackerlist" => Ok(TorrentFields::TrackerList),
             "trackerstats" => Ok(TorrentFields::TrackerStats),
             "totalsize" => Ok(TorrentFields::TotalSize),
             "torrentfile" => Ok(TorrentFields::TorrentFile),
@@ -180,7 +188,42 @@ impl TryFrom<&str> for TorrentFields {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "i64", into = "i64")]
+pub enum TorrentStatus {
+    Stopped = 0,
+    CheckWait = 1,
+    Check = 2,
+    DownloadWait = 3,
+    Download = 4,
+    SeedWait = 5,
+    Seed = 6,
+}
+
+impl TryFrom<i64> for TorrentStatus {
+    type Error = Error;
+
+    fn try_from(value: i64) -> Result<Self, Error> {
+        match value {
+            0 => Ok(TorrentStatus::Stopped),
+            1 => Ok(TorrentStatus::CheckWait),
+            2 => Ok(TorrentStatus::Check),
+            3 => Ok(TorrentStatus::DownloadWait),
+            4 => Ok(TorrentStatus::Download),
+            5 => Ok(TorrentStatus::SeedWait),
+            6 => Ok(TorrentStatus::Seed),
+            _ => Err(Error::UnknownTorrentStatus(value)),
+        }
+    }
+}
+
+impl From<TorrentStatus> for i64 {
+    fn from(status: TorrentStatus) -> Self {
+        status as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct File {
     pub bytes_completed: i64,
@@ -188,7 +231,7 @@ pub struct File {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileStats {
     pub bytes_completed: i64,
@@ -196,13 +239,7 @@ pub struct FileStats {
     pub priority: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Label {
-    pub label: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Peer {
     pub address: String,
@@ -223,7 +260,7 @@ pub struct Peer {
     pub rate_to_peer: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PeerFrom {
     pub from_cache: i64,
@@ -234,7 +271,7 @@ pub struct PeerFrom {
     pub from_tracker: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Tracker {
     pub announce: String,
@@ -243,7 +280,7 @@ pub struct Tracker {
     pub tier: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TrackerStats {
     pub announce: String,
@@ -274,13 +311,13 @@ pub struct TrackerStats {
     pub tier: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Webseed {
     pub webseed: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Torrent {
     pub activity_date: Option<i64>,
@@ -313,7 +350,7 @@ pub struct Torrent {
     pub is_finished: Option<bool>,
     pub is_private: Option<bool>,
     pub is_stalled: Option<bool>,
-    pub labels: Option<Vec<Label>>,
+    pub labels: Option<Vec<String>>,
     pub left_until_done: Option<i64>,
     pub magnet_link: Option<String>,
     pub manual_announce_time: Option<i64>,
@@ -348,6 +385,7 @@ pub struct Torrent {
     pub start_date: Option<i64>,
     pub status: Option<i64>,
     pub trackers: Option<Vec<Tracker>>,
+    pub tracker_list: Option<String>,
     pub tracker_stats: Option<Vec<TrackerStats>>,
     pub total_size: Option<i64>,
     pub torrent_file: Option<String>,
@@ -360,7 +398,7 @@ pub struct Torrent {
     pub webseeds_sending_to_us: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TorrentAddResponse {
     pub hash_string: String,
@@ -382,6 +420,8 @@ pub struct TorrentSetArgs {
     #[serde(skip_serializing_if = "Option::is_none", rename = "files-unwanted")]
     pub files_unwanted: Option<File>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub honors_session_limits: Option<bool>,
     pub ids: Ids,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -411,7 +451,9 @@ pub struct TorrentSetArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tracker_remove: Option<Ids>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tracker_replace: Option<Ids>,
+    pub tracker_replace: Option<TrackerReplace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracker_list: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub upload_limit: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -425,7 +467,7 @@ pub struct TorrentGetArgs {
     pub fields: Vec<TorrentFields>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct TorrentAddArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -435,6 +477,8 @@ pub struct TorrentAddArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub metainfo: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub paused: Option<bool>,
@@ -443,52 +487,66 @@ pub struct TorrentAddArgs {
     #[serde(skip_serializing_if = "Option::is_none", rename = "bandwidthPriority")]
     pub bandwidth_priority: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub files_wanted: Option<i64>,
+    pub files_wanted: Option<Vec<i64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub files_unwanted: Option<i64>,
+    pub files_unwanted: Option<Vec<i64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub priority_high: Option<i64>,
+    pub priority_high: Option<Vec<i64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub priority_low: Option<i64>,
+    pub priority_low: Option<Vec<i64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub priority_normal: Option<i64>,
+    pub priority_normal: Option<Vec<i64>>,
 }
 
 impl TorrentAddArgs {
     pub fn from_meta(path: &str) -> Result<Self, Error> {
-        let metainfo = Some(file_to_metadata(path)?);
         Ok(TorrentAddArgs {
-            cookies: None,
-            download_dir: None,
-            filename: None,
-            metainfo,
-            paused: None,
-            peer_limit: None,
-            bandwidth_priority: None,
-            files_wanted: None,
-            files_unwanted: None,
-            priority_high: None,
-            priority_low: None,
-            priority_normal: None,
+            metainfo: Some(file_to_metadata(path)?),
+            ..TorrentAddArgs::default()
         })
     }
 
     pub fn from_file(path: &str) -> Result<Self, Error> {
         Ok(TorrentAddArgs {
-            cookies: None,
-            download_dir: None,
             filename: Some(path.to_string()),
-            metainfo: None,
-            paused: None,
-            peer_limit: None,
-            bandwidth_priority: None,
-            files_wanted: None,
-            files_unwanted: None,
-            priority_high: None,
-            priority_low: None,
-            priority_normal: None,
+            ..TorrentAddArgs::default()
         })
     }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        Ok(TorrentAddArgs {
+            metainfo: Some(bytes_to_metadata(data)?),
+            ..TorrentAddArgs::default()
+        })
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        TorrentAddArgs::from_bytes(&buffer)
+    }
+
+    pub async fn from_async_reader<R: AsyncRead + Unpin>(mut reader: R) -> Result<Self, Error> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await?;
+        TorrentAddArgs::from_bytes(&buffer)
+    }
+}
+
+impl TryFrom<&[u8]> for TorrentAddArgs {
+    type Error = Error;
+
+    fn try_from(data: &[u8]) -> Result<Self, Error> {
+        TorrentAddArgs::from_bytes(data)
+    }
+}
+
+impl TryFrom<Bytes> for TorrentAddArgs {
+    type Error = Error;
+
+    fn try_from(data: Bytes) -> Result<Self, Error> {
+        TorrentAddArgs::from_bytes(&data)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -517,13 +575,38 @@ pub struct TorrentRenamePathArgs {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TorrentGet {
     pub torrents: Vec<Torrent>,
+    pub removed: Option<Vec<i64>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TorrentAdd {
-    pub torrent: Option<TorrentAddResponse>,
+// The daemon answers with a "torrent-added" or a "torrent-duplicate" object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TorrentAdd {
+    #[serde(rename = "torrent-added")]
+    Added(TorrentAddResponse),
     #[serde(rename = "torrent-duplicate")]
-    pub torrent_duplicate: Option<TorrentAddResponse>,
+    Duplicate(TorrentAddResponse),
+}
+
+impl TorrentAdd {
+    pub fn response(&self) -> &TorrentAddResponse {
+        match self {
+            TorrentAdd::Added(response) | TorrentAdd::Duplicate(response) => response,
+        }
+    }
+
+    pub fn into_response(self) -> TorrentAddResponse {
+        match self {
+            TorrentAdd::Added(response) | TorrentAdd::Duplicate(response) => response,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.response().id
+    }
+
+    pub fn is_duplicate(&self) -> bool {
+        matches!(self, TorrentAdd::Duplicate(_))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -535,7 +618,7 @@ pub struct TorrentRenamePath {
 
 impl Client {
     pub async fn torrent_start(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::TorrentStart,
             arguments: value,
@@ -547,7 +630,7 @@ impl Client {
     }
 
     pub async fn torrent_start_now(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::TorrentStartNow,
             arguments: value,
@@ -559,7 +642,7 @@ impl Client {
     }
 
     pub async fn torrent_stop(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::TorrentStop,
             arguments: value,
@@ -571,7 +654,7 @@ impl Client {
     }
 
     pub async fn torrent_verify(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::TorrentVerify,
             arguments: value,
@@ -583,7 +666,7 @@ impl Client {
     }
 
     pub async fn torrent_reannounce(&mut self, args: Option<Ids>) -> Result<(), Error> {
-        let value = args.map(|args| json!(args));
+        let value = args.map(|ids| json!({ "ids": ids }));
         let request = RpcRequest {
             method: Method::TorrentReannounce,
             arguments: value,
@@ -662,7 +745,7 @@ impl Client {
         args: TorrentRenamePathArgs,
     ) -> Result<TorrentRenamePath, Error> {
         let request = RpcRequest {
-            method: Method::TorrentAdd,
+            method: Method::TorrentRenamePath,
             arguments: Some(json!(args)),
             tag: None,
         };
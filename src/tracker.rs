@@ -0,0 +1,352 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Id, Ids};
+use crate::torrent::{Torrent, TorrentFields, TorrentGetArgs, TorrentSetArgs, Tracker};
+
+pub const TRACKER_LIST_RPC_VERSION: i64 = 17;
+
+// The daemon reads tracker-replace as a flat list: id, url, id, url, ...
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackerReplace(pub Vec<(i64, String)>);
+
+impl From<Vec<(i64, String)>> for TrackerReplace {
+    fn from(pairs: Vec<(i64, String)>) -> Self {
+        TrackerReplace(pairs)
+    }
+}
+
+impl Serialize for TrackerReplace {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len() * 2))?;
+        for (id, announce) in &self.0 {
+            seq.serialize_element(id)?;
+            seq.serialize_element(announce)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TrackerReplace {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PairsVisitor;
+
+        impl<'de> Visitor<'de> for PairsVisitor {
+            type Value = TrackerReplace;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a flat list of tracker ids and announce urls")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut pairs = Vec::new();
+                while let Some(id) = seq.next_element::<i64>()? {
+                    let announce = seq
+                        .next_element::<String>()?
+                        .ok_or_else(|| de::Error::custom("tracker id without announce url"))?;
+                    pairs.push((id, announce));
+                }
+                Ok(TrackerReplace(pairs))
+            }
+        }
+
+        deserializer.deserialize_seq(PairsVisitor)
+    }
+}
+
+// Announce urls grouped by tier, the same shape as the trackerList string of rpc version 17:
+// one url per line with tiers separated by an empty line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackerList {
+    pub tiers: Vec<Vec<String>>,
+}
+
+impl TrackerList {
+    pub fn parse(list: &str) -> Self {
+        let mut tiers = Vec::new();
+        let mut tier = Vec::new();
+        for line in list.lines().map(str::trim) {
+            if line.is_empty() {
+                if !tier.is_empty() {
+                    tiers.push(std::mem::take(&mut tier));
+                }
+            } else if !tier.iter().any(|announce| announce == line) {
+                tier.push(line.to_string());
+            }
+        }
+        if !tier.is_empty() {
+            tiers.push(tier);
+        }
+        TrackerList { tiers }
+    }
+
+    pub fn from_trackers(trackers: &[Tracker]) -> Self {
+        let mut sorted = trackers.to_vec();
+        sorted.sort_by_key(|tracker| (tracker.tier, tracker.id));
+        let mut tiers: Vec<Vec<String>> = Vec::new();
+        let mut last_tier = None;
+        for tracker in sorted {
+            if last_tier != Some(tracker.tier) {
+                tiers.push(Vec::new());
+                last_tier = Some(tracker.tier);
+            }
+            if let Some(tier) = tiers.last_mut() {
+                tier.push(tracker.announce);
+            }
+        }
+        TrackerList { tiers }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiers.is_empty()
+    }
+
+    pub fn announces(&self) -> impl Iterator<Item = &String> {
+        self.tiers.iter().flatten()
+    }
+
+    pub fn contains(&self, announce: &str) -> bool {
+        self.announces().any(|url| url == announce)
+    }
+
+    pub fn tier_of(&self, announce: &str) -> Option<usize> {
+        self.tiers
+            .iter()
+            .position(|tier| tier.iter().any(|url| url == announce))
+    }
+
+    // A tier past the last one, or None, opens a new tier at the end
+    pub fn add(&mut self, announce: &str, tier: Option<usize>) -> bool {
+        if self.contains(announce) {
+            return false;
+        }
+        match tier.and_then(|tier| self.tiers.get_mut(tier)) {
+            Some(tier) => tier.push(announce.to_string()),
+            None => self.tiers.push(vec![announce.to_string()]),
+        }
+        true
+    }
+
+    // Urls of other that are new here keep their tier grouping in tiers appended at the end
+    pub fn merge(&mut self, other: &TrackerList) -> bool {
+        let mut merged = false;
+        for tier in &other.tiers {
+            let mut new_tier: Vec<String> = Vec::new();
+            for announce in tier {
+                if !self.contains(announce) && !new_tier.contains(announce) {
+                    new_tier.push(announce.clone());
+                }
+            }
+            if !new_tier.is_empty() {
+                self.tiers.push(new_tier);
+                merged = true;
+            }
+        }
+        merged
+    }
+
+    pub fn remove(&mut self, announce: &str) -> bool {
+        let mut removed = false;
+        for tier in &mut self.tiers {
+            let len = tier.len();
+            tier.retain(|url| url != announce);
+            removed |= tier.len() != len;
+        }
+        self.tiers.retain(|tier| !tier.is_empty());
+        removed
+    }
+
+    pub fn replace(&mut self, old: &str, new: &str) -> bool {
+        if old == new || !self.contains(old) {
+            return false;
+        }
+        if self.contains(new) {
+            return self.remove(old);
+        }
+        for url in self.tiers.iter_mut().flatten() {
+            if url == old {
+                *url = new.to_string();
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for TrackerList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tiers: Vec<String> = self.tiers.iter().map(|tier| tier.join("\n")).collect();
+        write!(f, "{}", tiers.join("\n\n"))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TrackerEdit<'a> {
+    Add(&'a str, Option<usize>),
+    Remove(&'a str),
+    Replace(&'a str, &'a str),
+}
+
+fn tracker_id(torrent: &Torrent, announce: &str) -> Option<i64> {
+    torrent
+        .trackers
+        .as_ref()?
+        .iter()
+        .find(|tracker| tracker.announce == announce)
+        .map(|tracker| tracker.id)
+}
+
+impl Client {
+    pub async fn add_tracker(
+        &mut self,
+        ids: Ids,
+        announce: &str,
+        tier: Option<usize>,
+    ) -> Result<Vec<i64>, Error> {
+        self.edit_trackers(ids, TrackerEdit::Add(announce, tier))
+            .await
+    }
+
+    pub async fn remove_tracker(&mut self, ids: Ids, announce: &str) -> Result<Vec<i64>, Error> {
+        self.edit_trackers(ids, TrackerEdit::Remove(announce)).await
+    }
+
+    pub async fn replace_tracker(
+        &mut self,
+        ids: Ids,
+        old: &str,
+        new: &str,
+    ) -> Result<Vec<i64>, Error> {
+        self.edit_trackers(ids, TrackerEdit::Replace(old, new))
+            .await
+    }
+
+    pub async fn set_tracker_list(&mut self, id: i64, list: &TrackerList) -> Result<(), Error> {
+        let version = self.rpc_version().await?;
+        if version < TRACKER_LIST_RPC_VERSION {
+            return Err(Error::TrackerTierUnsupported(version));
+        }
+        self.torrent_set(TorrentSetArgs {
+            ids: Ids::Id(id),
+            tracker_list: Some(list.to_string()),
+            ..TorrentSetArgs::default()
+        })
+        .await
+    }
+
+    // Adds the trackers of list the torrent doesn't have yet, returns whether any was added.
+    // Without trackerList support every new url lands in its own tier.
+    pub async fn merge_trackers(&mut self, id: i64, list: &TrackerList) -> Result<bool, Error> {
+        let version = self.rpc_version().await?;
+        let torrents = self
+            .torrent_get(TorrentGetArgs {
+                ids: Some(Ids::Id(id)),
+                fields: vec![TorrentFields::Id, TorrentFields::Trackers],
+            })
+            .await?
+            .torrents;
+        let torrent = torrents.first().ok_or(Error::TorrentNotFound)?;
+        let trackers = torrent.trackers.as_deref().unwrap_or_default();
+        let mut current = TrackerList::from_trackers(trackers);
+        let known = current.clone();
+        if !current.merge(list) {
+            return Ok(false);
+        }
+        let mut args = TorrentSetArgs {
+            ids: Ids::Id(id),
+            ..TorrentSetArgs::default()
+        };
+        if version >= TRACKER_LIST_RPC_VERSION {
+            args.tracker_list = Some(current.to_string());
+        } else {
+            let added = current
+                .announces()
+                .filter(|announce| !known.contains(announce))
+                .cloned()
+                .collect();
+            args.tracker_add = Some(added);
+        }
+        self.torrent_set(args).await?;
+        Ok(true)
+    }
+
+    // Returns the ids of the torrents whose trackers were changed
+    async fn edit_trackers(&mut self, ids: Ids, edit: TrackerEdit<'_>) -> Result<Vec<i64>, Error> {
+        let version = self.rpc_version().await?;
+        let has_tracker_list = version >= TRACKER_LIST_RPC_VERSION;
+        if let TrackerEdit::Add(_, Some(_)) = edit {
+            if !has_tracker_list {
+                return Err(Error::TrackerTierUnsupported(version));
+            }
+        }
+        let torrents = self
+            .torrent_get(TorrentGetArgs {
+                ids: Some(ids),
+                fields: vec![TorrentFields::Id, TorrentFields::Trackers],
+            })
+            .await?
+            .torrents;
+        let mut changed = Vec::new();
+        for torrent in torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            let trackers = torrent.trackers.as_deref().unwrap_or_default();
+            let mut list = TrackerList::from_trackers(trackers);
+            let edited = match edit {
+                TrackerEdit::Add(announce, tier) => list.add(announce, tier),
+                TrackerEdit::Remove(announce) => list.remove(announce),
+                TrackerEdit::Replace(old, new) => list.replace(old, new),
+            };
+            if !edited {
+                continue;
+            }
+            let mut args = TorrentSetArgs {
+                ids: Ids::Id(id),
+                ..TorrentSetArgs::default()
+            };
+            if has_tracker_list {
+                args.tracker_list = Some(list.to_string());
+            } else {
+                match edit {
+                    TrackerEdit::Add(announce, _) => {
+                        args.tracker_add = Some(vec![announce.to_string()]);
+                    }
+                    TrackerEdit::Remove(announce) => {
+                        let tracker_ids = trackers
+                            .iter()
+                            .filter(|tracker| tracker.announce == announce)
+                            .map(|tracker| Id::Id(tracker.id))
+                            .collect();
+                        args.tracker_remove = Some(Ids::Array(tracker_ids));
+                    }
+                    TrackerEdit::Replace(old, new) => {
+                        let old_id = tracker_id(&torrent, old).unwrap_or_default();
+                        if tracker_id(&torrent, new).is_some() {
+                            args.tracker_remove = Some(Ids::Array(vec![Id::Id(old_id)]));
+                        } else {
+                            args.tracker_replace =
+                                Some(TrackerReplace(vec![(old_id, new.to_string())]));
+                        }
+                    }
+                }
+            }
+            self.torrent_set(args).await?;
+            changed.push(id);
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,135 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::session::{SessionFields, SessionGetArgs, Units};
+
+impl Default for Units {
+    fn default() -> Self {
+        Units {
+            speed_units: vec![
+                "kB/s".to_string(),
+                "MB/s".to_string(),
+                "GB/s".to_string(),
+                "TB/s".to_string(),
+            ],
+            speed_bytes: 1000,
+            size_units: vec![
+                "kB".to_string(),
+                "MB".to_string(),
+                "GB".to_string(),
+                "TB".to_string(),
+            ],
+            size_bytes: 1000,
+            memory_units: vec![
+                "KiB".to_string(),
+                "MiB".to_string(),
+                "GiB".to_string(),
+                "TiB".to_string(),
+            ],
+            memory_bytes: 1024,
+        }
+    }
+}
+
+fn unit_name(names: &[String], index: usize) -> &str {
+    names
+        .get(index)
+        .or_else(|| names.last())
+        .map(|name| name.as_str())
+        .unwrap_or("")
+}
+
+// Same rules as libtransmission's size formatter: pick the largest unit not above the value,
+// two decimals below 100 and one decimal above
+fn format_bytes(names: &[String], multiplier: i64, bytes: i64) -> String {
+    let kilo = multiplier.max(1) as f64;
+    let bytes = bytes as f64;
+    let count = names.len().max(1);
+    let mut index = 0;
+    let mut unit = kilo;
+    while index + 1 < count && bytes.abs() >= unit * kilo {
+        index += 1;
+        unit *= kilo;
+    }
+    let value = bytes / unit;
+    let precision = if value.abs() < 100.0 { 2 } else { 1 };
+    format!("{:.*} {}", precision, value, unit_name(names, index))
+}
+
+fn parse_bytes(names: &[String], multiplier: i64, value: &str) -> Result<f64, Error> {
+    let wrong = || Error::WrongUnitValue(value.to_string());
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number.replace(',', ".").parse().map_err(|_| wrong())?;
+    let unit = unit.trim();
+    let index = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(unit))
+        .ok_or_else(wrong)?;
+    Ok(number * (multiplier as f64).powi(index as i32))
+}
+
+impl Units {
+    pub fn format_speed(&self, bytes_per_second: i64) -> String {
+        self.format_speed_kbps(bytes_per_second as f64 / self.speed_bytes.max(1) as f64)
+    }
+
+    // Same rules as libtransmission's speed formatter, the input is in the daemon's KBps
+    pub fn format_speed_kbps(&self, kbps: f64) -> String {
+        let kilo = self.speed_bytes.max(1) as f64;
+        let names = &self.speed_units;
+        if kbps.abs() <= 999.95 {
+            return format!("{} {}", kbps as i64, unit_name(names, 0));
+        }
+        let mut speed = kbps / kilo;
+        if speed.abs() <= 99.995 {
+            return format!("{:.2} {}", speed, unit_name(names, 1));
+        }
+        if speed.abs() <= 999.95 {
+            return format!("{:.1} {}", speed, unit_name(names, 1));
+        }
+        let mut index = 2;
+        speed /= kilo;
+        while index + 1 < names.len() && speed.abs() > 999.95 {
+            index += 1;
+            speed /= kilo;
+        }
+        format!("{:.1} {}", speed, unit_name(names, index))
+    }
+
+    pub fn format_size(&self, bytes: i64) -> String {
+        format_bytes(&self.size_units, self.size_bytes, bytes)
+    }
+
+    pub fn format_memory(&self, bytes: i64) -> String {
+        format_bytes(&self.memory_units, self.memory_bytes, bytes)
+    }
+
+    // Returns KBps, the unit of speed-limit-down, speed-limit-up and the alt speeds
+    pub fn parse_speed(&self, value: &str) -> Result<i64, Error> {
+        Ok(parse_bytes(&self.speed_units, self.speed_bytes, value)?.round() as i64)
+    }
+
+    pub fn parse_size(&self, value: &str) -> Result<i64, Error> {
+        let bytes = parse_bytes(&self.size_units, self.size_bytes, value)?;
+        Ok((bytes * self.size_bytes as f64).round() as i64)
+    }
+
+    pub fn parse_memory(&self, value: &str) -> Result<i64, Error> {
+        let bytes = parse_bytes(&self.memory_units, self.memory_bytes, value)?;
+        Ok((bytes * self.memory_bytes as f64).round() as i64)
+    }
+}
+
+impl Client {
+    pub async fn units(&mut self) -> Result<Units, Error> {
+        let session = self
+            .session_get(Some(SessionGetArgs {
+                fields: vec![SessionFields::Units],
+            }))
+            .await?;
+        session.units.ok_or(Error::NoArguments)
+    }
+}
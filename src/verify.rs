@@ -0,0 +1,323 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha1::{Digest, Sha1};
+
+use crate::error::Error;
+use crate::metainfo::{safe_component, Metainfo};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileState {
+    Complete,
+    Missing,
+    // Size on disk differs from the metainfo
+    WrongSize(u64),
+    // A piece touching the file fails its hash, possibly because of a neighbouring file
+    Corrupt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCheck {
+    pub path: PathBuf,
+    pub length: i64,
+    pub state: FileState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub pieces: Vec<bool>,
+    pub files: Vec<FileCheck>,
+}
+
+// Pieces packed high bit first, the layout of Torrent.pieces before base64
+pub fn pack_bitfield(pieces: &[bool]) -> Vec<u8> {
+    let mut bitfield = vec![0u8; pieces.len().div_ceil(8)];
+    for (index, _) in pieces.iter().enumerate().filter(|(_, have)| **have) {
+        bitfield[index / 8] |= 0x80 >> (index % 8);
+    }
+    bitfield
+}
+
+// Torrent.pieces as one flag per piece
+pub fn decode_bitfield(pieces: &str, piece_count: usize) -> Result<Vec<bool>, Error> {
+    let bitfield = STANDARD
+        .decode(pieces)
+        .map_err(|err| Error::WrongBitfield(err.to_string()))?;
+    if bitfield.len() != piece_count.div_ceil(8) {
+        return Err(Error::WrongBitfield(format!(
+            "{} bytes for {} pieces",
+            bitfield.len(),
+            piece_count
+        )));
+    }
+    Ok((0..piece_count)
+        .map(|index| bitfield[index / 8] & (0x80 >> (index % 8)) != 0)
+        .collect())
+}
+
+impl VerifyReport {
+    pub fn have_count(&self) -> usize {
+        self.pieces.iter().filter(|have| **have).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pieces.iter().all(|have| *have)
+    }
+
+    pub fn missing_files(&self) -> impl Iterator<Item = &FileCheck> {
+        self.files
+            .iter()
+            .filter(|file| file.state == FileState::Missing)
+    }
+
+    pub fn corrupt_files(&self) -> impl Iterator<Item = &FileCheck> {
+        self.files
+            .iter()
+            .filter(|file| matches!(file.state, FileState::Corrupt | FileState::WrongSize(_)))
+    }
+
+    pub fn bitfield(&self) -> Vec<u8> {
+        pack_bitfield(&self.pieces)
+    }
+
+    // Same encoding as Torrent.pieces, so the two can be compared as strings
+    pub fn pieces_base64(&self) -> String {
+        STANDARD.encode(self.bitfield())
+    }
+
+    // Indices where the daemon's Torrent.pieces disagrees with this check
+    pub fn differing_pieces(&self, pieces: &str) -> Result<Vec<usize>, Error> {
+        let daemon = decode_bitfield(pieces, self.pieces.len())?;
+        Ok(daemon
+            .iter()
+            .zip(&self.pieces)
+            .enumerate()
+            .filter(|(_, (daemon, local))| daemon != local)
+            .map(|(index, _)| index)
+            .collect())
+    }
+}
+
+// A run of bytes of one file inside a piece
+struct Segment {
+    file: usize,
+    offset: u64,
+    length: usize,
+}
+
+// Checks downloaded data against the v1 piece hashes without asking the daemon
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    metainfo: Metainfo,
+    download_dir: PathBuf,
+    threads: usize,
+}
+
+impl Verifier {
+    // download_dir is the folder holding the torrent, like Torrent.downloadDir
+    pub fn new<P: AsRef<Path>>(metainfo: Metainfo, download_dir: P) -> Self {
+        Verifier {
+            metainfo,
+            download_dir: download_dir.as_ref().to_path_buf(),
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Where the file is on disk. Metainfo fields are public, so the names are checked
+    // again here, and a file reached through a symlink has to stay in download_dir.
+    pub fn file_path(&self, index: usize) -> Result<PathBuf, Error> {
+        let escapes = || {
+            Error::WrongMetainfo(format!(
+                "file {} leaves the download directory",
+                self.metainfo.files[index].path_string()
+            ))
+        };
+        let mut parts = Vec::new();
+        if self.metainfo.multi_file {
+            parts.push(&self.metainfo.name);
+        }
+        parts.extend(&self.metainfo.files[index].path);
+        if !parts.iter().all(|part| safe_component(part)) {
+            return Err(escapes());
+        }
+        let path: PathBuf = self
+            .download_dir
+            .iter()
+            .chain(parts.iter().map(|part| part.as_ref()))
+            .collect();
+        if let (Ok(resolved), Ok(root)) = (
+            std::fs::canonicalize(&path),
+            std::fs::canonicalize(&self.download_dir),
+        ) {
+            if !resolved.starts_with(root) {
+                return Err(escapes());
+            }
+        }
+        Ok(path)
+    }
+
+    fn segments(&self, piece: usize) -> Vec<Segment> {
+        let piece_length = self.metainfo.piece_length as u64;
+        let start = piece as u64 * piece_length;
+        let end = start.saturating_add(piece_length);
+        let mut segments = Vec::new();
+        let mut file_start = 0;
+        for (index, file) in self.metainfo.files.iter().enumerate() {
+            let file_end = file_start + file.length as u64;
+            if file_end > start && file_start < end {
+                let from = start.max(file_start);
+                segments.push(Segment {
+                    file: index,
+                    offset: from - file_start,
+                    length: (end.min(file_end) - from) as usize,
+                });
+            }
+            file_start = file_end;
+        }
+        segments
+    }
+
+    // Padding files are zeros by definition and are never read. The worker keeps the
+    // last file open, pieces mostly continue in the file the previous one ended in.
+    fn check_piece(
+        &self,
+        piece: usize,
+        paths: &[PathBuf],
+        open: &mut Option<(usize, File)>,
+        buffer: &mut Vec<u8>,
+    ) -> bool {
+        buffer.clear();
+        for segment in self.segments(piece) {
+            let start = buffer.len();
+            buffer.resize(start + segment.length, 0);
+            if self.metainfo.files[segment.file].padding {
+                continue;
+            }
+            if open.as_ref().map(|(index, _)| *index) != Some(segment.file) {
+                *open = File::open(&paths[segment.file])
+                    .ok()
+                    .map(|file| (segment.file, file));
+            }
+            let Some((_, file)) = open else {
+                return false;
+            };
+            let read = file
+                .seek(SeekFrom::Start(segment.offset))
+                .and_then(|_| file.read_exact(&mut buffer[start..]));
+            if read.is_err() {
+                return false;
+            }
+        }
+        Some(Sha1::digest(&buffer[..]).as_slice()) == self.metainfo.piece_hash(piece)
+    }
+
+    fn check_pieces(&self, paths: &[PathBuf]) -> Vec<bool> {
+        let count = self.metainfo.piece_count();
+        let next = AtomicUsize::new(0);
+        let mut pieces = vec![false; count];
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.min(count.max(1)))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut buffer = Vec::with_capacity(self.metainfo.piece_length as usize);
+                        let mut open = None;
+                        let mut checked = Vec::new();
+                        loop {
+                            let piece = next.fetch_add(1, Ordering::Relaxed);
+                            if piece >= count {
+                                return checked;
+                            }
+                            let have = self.check_piece(piece, paths, &mut open, &mut buffer);
+                            checked.push((piece, have));
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                let checked = worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (piece, have) in checked {
+                    pieces[piece] = have;
+                }
+            }
+        });
+        pieces
+    }
+
+    // Blocks until every piece is read, run it with spawn_blocking from async code
+    pub fn verify(&self) -> Result<VerifyReport, Error> {
+        // Metainfo fields are public, so the piece layout is checked before it is sliced
+        let piece_length = u64::try_from(self.metainfo.piece_length)
+            .ok()
+            .filter(|piece_length| *piece_length > 0)
+            .ok_or_else(|| Error::WrongMetainfo("piece length is not positive".to_string()))?;
+        let total = self
+            .metainfo
+            .files
+            .iter()
+            .try_fold(0u64, |total, file| {
+                u64::try_from(file.length)
+                    .ok()
+                    .and_then(|length| total.checked_add(length))
+            })
+            .ok_or_else(|| Error::WrongMetainfo("bad total size".to_string()))?;
+        if self.metainfo.piece_count() == 0 && total > 0 {
+            return Err(Error::WrongMetainfo(
+                "no v1 piece hashes to verify against".to_string(),
+            ));
+        }
+        if self.metainfo.piece_count() as u64 != total.div_ceil(piece_length) {
+            return Err(Error::WrongMetainfo(
+                "piece count does not match the total size".to_string(),
+            ));
+        }
+        let paths = (0..self.metainfo.files.len())
+            .map(|index| self.file_path(index))
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        let pieces = self.check_pieces(&paths);
+        let mut files = Vec::new();
+        let mut file_start = 0;
+        for (index, file) in self.metainfo.files.iter().enumerate() {
+            let length = file.length as u64;
+            let file_end = file_start + length;
+            let file_pieces = if length == 0 {
+                0..0
+            } else {
+                (file_start / piece_length) as usize..file_end.div_ceil(piece_length) as usize
+            };
+            file_start = file_end;
+            if file.padding {
+                continue;
+            }
+            let path = paths[index].clone();
+            let state = match std::fs::metadata(&path) {
+                Err(_) => FileState::Missing,
+                Ok(metadata) if !metadata.is_file() => FileState::Missing,
+                Ok(metadata) if metadata.len() != length => FileState::WrongSize(metadata.len()),
+                Ok(_) if pieces[file_pieces].iter().all(|have| *have) => FileState::Complete,
+                Ok(_) => FileState::Corrupt,
+            };
+            files.push(FileCheck {
+                path,
+                length: file.length,
+                state,
+            });
+        }
+        Ok(VerifyReport { pieces, files })
+    }
+}
+
+impl Metainfo {
+    pub fn verify<P: AsRef<Path>>(&self, download_dir: P) -> Result<VerifyReport, Error> {
+        Verifier::new(self.clone(), download_dir).verify()
+    }
+}
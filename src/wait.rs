@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use tokio::time::{sleep, Instant};
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::Id;
+use crate::torrent::{Torrent, TorrentFields, TorrentStatus};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WaitOptions {
+    pub timeout: Duration,
+    pub interval: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        WaitOptions {
+            timeout: Duration::from_secs(600),
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn new(timeout: Duration, interval: Duration) -> Self {
+        WaitOptions { timeout, interval }
+    }
+}
+
+pub fn metadata_complete(torrent: &Torrent) -> bool {
+    torrent
+        .metadata_percent_complete
+        .is_some_and(|percent| percent >= 1.0)
+}
+
+// The daemon switches the status to check-wait before torrent-verify returns,
+// so a torrent that is not checking has nothing left to verify
+pub fn verify_finished(torrent: &Torrent) -> bool {
+    match torrent.status.map(TorrentStatus::try_from) {
+        Some(Ok(TorrentStatus::CheckWait)) | Some(Ok(TorrentStatus::Check)) => false,
+        Some(_) => true,
+        None => false,
+    }
+}
+
+pub fn download_complete(torrent: &Torrent) -> bool {
+    metadata_complete(torrent) && torrent.percent_done.is_some_and(|percent| percent >= 1.0)
+}
+
+pub fn location_reached(torrent: &Torrent, location: &str) -> bool {
+    let location = location.trim_end_matches(['/', '\\']);
+    torrent
+        .download_dir
+        .as_deref()
+        .is_some_and(|dir| dir.trim_end_matches(['/', '\\']) == location)
+}
+
+impl Client {
+    // Polls the torrent with the given fields until the predicate holds, returns the last snapshot
+    pub async fn wait_until<I, F>(
+        &mut self,
+        id: I,
+        fields: Vec<TorrentFields>,
+        options: WaitOptions,
+        mut predicate: F,
+    ) -> Result<Torrent, Error>
+    where
+        I: Into<Id>,
+        F: FnMut(&Torrent) -> bool,
+    {
+        let deadline = Instant::now() + options.timeout;
+        let mut handle = self.torrent(id);
+        loop {
+            let torrent = handle.refresh(fields.clone()).await?;
+            if predicate(&torrent) {
+                return Ok(torrent);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::Timeout);
+            }
+            sleep(options.interval.min(deadline - now)).await;
+        }
+    }
+
+    pub async fn wait_for_metadata<I: Into<Id>>(
+        &mut self,
+        id: I,
+        options: WaitOptions,
+    ) -> Result<Torrent, Error> {
+        let fields = vec![TorrentFields::Id, TorrentFields::MetadataPercentComplete];
+        self.wait_until(id, fields, options, metadata_complete)
+            .await
+    }
+
+    pub async fn wait_for_verify<I: Into<Id>>(
+        &mut self,
+        id: I,
+        options: WaitOptions,
+    ) -> Result<Torrent, Error> {
+        let fields = vec![
+            TorrentFields::Id,
+            TorrentFields::Status,
+            TorrentFields::RecheckProgress,
+        ];
+        self.wait_until(id, fields, options, verify_finished).await
+    }
+
+    pub async fn wait_for_location<I: Into<Id>>(
+        &mut self,
+        id: I,
+        location: &str,
+        options: WaitOptions,
+    ) -> Result<Torrent, Error> {
+        let fields = vec![TorrentFields::Id, TorrentFields::DownloadDir];
+        self.wait_until(id, fields, options, |torrent| {
+            location_reached(torrent, location)
+        })
+        .await
+    }
+
+    pub async fn wait_for_completion<I: Into<Id>>(
+        &mut self,
+        id: I,
+        options: WaitOptions,
+    ) -> Result<Torrent, Error> {
+        let fields = vec![
+            TorrentFields::Id,
+            TorrentFields::MetadataPercentComplete,
+            TorrentFields::PercentDone,
+        ];
+        self.wait_until(id, fields, options, download_complete)
+            .await
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tokio::fs;
+use tokio::time::sleep;
+
+use crate::add::is_rejection;
+use crate::client::Client;
+use crate::error::Error;
+use crate::magnet::MagnetLink;
+use crate::torrent::{TorrentAdd, TorrentAddArgs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboxKind {
+    Torrent,
+    Magnet,
+}
+
+// Browsers and copy tools write to temporary names first, those are never picked up
+pub fn inbox_kind(path: &Path) -> Option<InboxKind> {
+    let name = path.file_name()?.to_str()?;
+    if name.starts_with('.') || name.starts_with('~') {
+        return None;
+    }
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "torrent" => Some(InboxKind::Torrent),
+        "magnet" => Some(InboxKind::Magnet),
+        _ => None,
+    }
+}
+
+// The first line of a .magnet file that holds a magnet link
+pub fn read_magnet_file(text: &str) -> Result<MagnetLink, Error> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| Error::WrongMagnet("empty magnet file".to_string()))?
+        .parse()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchFolder {
+    path: PathBuf,
+    done_dir: PathBuf,
+    failed_dir: PathBuf,
+    download_dir: Option<String>,
+    labels: Vec<String>,
+    paused: Option<bool>,
+}
+
+impl WatchFolder {
+    // Imported files go to the done and failed subfolders unless set otherwise
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        WatchFolder {
+            done_dir: path.join("done"),
+            failed_dir: path.join("failed"),
+            path,
+            download_dir: None,
+            labels: Vec::new(),
+            paused: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn done_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.done_dir = path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn failed_dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.failed_dir = path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn download_dir(mut self, download_dir: &str) -> Self {
+        self.download_dir = Some(download_dir.to_string());
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = Some(paused);
+        self
+    }
+
+    // The add arguments for one inbox file with the folder options applied
+    pub async fn add_args(&self, path: &Path) -> Result<TorrentAddArgs, Error> {
+        let mut builder = TorrentAddArgs::builder();
+        builder = match inbox_kind(path) {
+            Some(InboxKind::Torrent) => builder.metainfo(&fs::read(path).await?),
+            Some(InboxKind::Magnet) => {
+                builder.magnet(&read_magnet_file(&fs::read_to_string(path).await?)?)
+            }
+            None => return Err(Error::NoFileMeta),
+        };
+        if let Some(download_dir) = &self.download_dir {
+            builder = builder.download_dir(download_dir);
+        }
+        if let Some(paused) = self.paused {
+            builder = builder.paused(paused);
+        }
+        for label in &self.labels {
+            builder = builder.label(label);
+        }
+        builder.build()
+    }
+}
+
+#[derive(Debug)]
+pub struct ImportEvent {
+    pub path: PathBuf,
+    // None when moving the file failed, the error is in the batch
+    pub moved_to: Option<PathBuf>,
+    pub result: Result<TorrentAdd, Error>,
+}
+
+// A watch folder that could not be listed, the other folders are still scanned
+#[derive(Debug)]
+pub struct FolderError {
+    pub folder: PathBuf,
+    pub error: Error,
+}
+
+// The imports of one poll, the folders it could not scan and the error that stopped it early
+#[derive(Debug, Default)]
+pub struct ImportBatch {
+    pub events: Vec<ImportEvent>,
+    pub folder_errors: Vec<FolderError>,
+    pub error: Option<Error>,
+}
+
+// Moves file into dir, numbering the name when it is taken
+async fn move_into(file: &Path, dir: &Path) -> Result<PathBuf, Error> {
+    fs::create_dir_all(dir).await?;
+    let name = file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let mut target = dir.join(&name);
+    let mut number = 1;
+    while fs::try_exists(&target).await? {
+        target = dir.join(format!("{}.{}", name, number));
+        number += 1;
+    }
+    fs::rename(file, &target).await?;
+    Ok(target)
+}
+
+async fn write_error_note(moved_to: &Path, err: &Error) -> Result<(), Error> {
+    let mut note = moved_to.as_os_str().to_os_string();
+    note.push(".error");
+    fs::write(note, format!("{}\n", err)).await?;
+    Ok(())
+}
+
+type Signature = (u64, Option<SystemTime>);
+
+// Inbox files of one folder with their size and modification time
+async fn scan_folder(folder: &Path) -> Result<Vec<(PathBuf, Signature)>, Error> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(folder).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if inbox_kind(&path).is_none() {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            files.push((path, (metadata.len(), metadata.modified().ok())));
+        }
+    }
+    Ok(files)
+}
+
+// Polls the watch folders and adds new files. A file is only read once its size and
+// modification time stayed the same between two scans, so half-written files are left alone.
+#[derive(Debug)]
+pub struct FolderImporter {
+    folders: Vec<WatchFolder>,
+    interval: Duration,
+    seen: HashMap<PathBuf, Signature>,
+}
+
+impl FolderImporter {
+    pub fn new(interval: Duration) -> Self {
+        FolderImporter {
+            folders: Vec::new(),
+            interval,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn folder(mut self, folder: WatchFolder) -> Self {
+        self.folders.push(folder);
+        self
+    }
+
+    pub fn folders(&self) -> &[WatchFolder] {
+        &self.folders
+    }
+
+    // Files that are stable since the previous scan, with the index of their folder,
+    // and the folders that could not be read
+    pub async fn scan(&mut self) -> (Vec<(usize, PathBuf)>, Vec<FolderError>) {
+        let mut ready = Vec::new();
+        let mut errors = Vec::new();
+        let mut seen = HashMap::new();
+        for (index, folder) in self.folders.iter().enumerate() {
+            let files = match scan_folder(&folder.path).await {
+                Ok(files) => files,
+                Err(error) => {
+                    errors.push(FolderError {
+                        folder: folder.path.clone(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+            for (path, signature) in files {
+                if signature.0 > 0 && self.seen.get(&path) == Some(&signature) {
+                    ready.push((index, path.clone()));
+                }
+                seen.insert(path, signature);
+            }
+        }
+        ready.sort();
+        self.seen = seen;
+        (ready, errors)
+    }
+
+    // Files that can't be read or that the daemon rejects go to the failed folder with a
+    // .error note next to them. Connection errors stop the poll and leave the file for the
+    // next one, the imports done before are still in the batch.
+    pub async fn poll(&mut self, client: &mut Client) -> ImportBatch {
+        let (ready, folder_errors) = self.scan().await;
+        let mut batch = ImportBatch {
+            folder_errors,
+            ..ImportBatch::default()
+        };
+        for (index, path) in ready {
+            let folder = &self.folders[index];
+            let (result, local) = match folder.add_args(&path).await {
+                Ok(args) => (client.torrent_add(args).await, false),
+                Err(err) => (Err(err), true),
+            };
+            let dir = match &result {
+                Ok(_) => &folder.done_dir,
+                Err(err) if local || is_rejection(err) => &folder.failed_dir,
+                Err(_) => {
+                    batch.error = result.err();
+                    break;
+                }
+            };
+            let moved = match (move_into(&path, dir).await, &result) {
+                (Ok(moved_to), Err(err)) => {
+                    write_error_note(&moved_to, err).await.map(|_| moved_to)
+                }
+                (moved, _) => moved,
+            };
+            self.seen.remove(&path);
+            match moved {
+                Ok(moved_to) => batch.events.push(ImportEvent {
+                    path,
+                    moved_to: Some(moved_to),
+                    result,
+                }),
+                Err(err) => {
+                    batch.events.push(ImportEvent {
+                        path,
+                        moved_to: None,
+                        result,
+                    });
+                    batch.error = Some(err);
+                    break;
+                }
+            }
+        }
+        batch
+    }
+
+    // Imports until a connection error, handing every import to on_event. A folder that
+    // can't be read is logged and tried again on the next poll.
+    pub async fn run<F>(mut self, mut client: Client, mut on_event: F) -> Result<(), Error>
+    where
+        F: FnMut(ImportEvent),
+    {
+        loop {
+            let batch = self.poll(&mut client).await;
+            for event in batch.events {
+                on_event(event);
+            }
+            for folder_error in batch.folder_errors {
+                log::warn!(
+                    "can't scan watch folder {}: {}",
+                    folder_error.folder.display(),
+                    folder_error.error
+                );
+            }
+            if let Some(err) = batch.error {
+                return Err(err);
+            }
+            sleep(self.interval).await;
+        }
+    }
+}
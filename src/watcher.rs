@@ -0,0 +1,249 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::stream::{self, Stream};
+use tokio::time::{sleep, Instant};
+
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::{Ids, RECENTLY_ACTIVE_WINDOW};
+use crate::torrent::{Torrent, TorrentFields, TorrentGetArgs, TorrentStatus};
+
+pub const WATCHER_FIELDS: [TorrentFields; 9] = [
+    TorrentFields::Id,
+    TorrentFields::HashString,
+    TorrentFields::Name,
+    TorrentFields::Status,
+    TorrentFields::PercentDone,
+    TorrentFields::Error,
+    TorrentFields::ErrorString,
+    TorrentFields::Labels,
+    TorrentFields::DownloadDir,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TorrentEvent {
+    Added(Torrent),
+    Removed(Torrent),
+    Started(Torrent),
+    Stopped(Torrent),
+    Completed(Torrent),
+    Verified(Torrent),
+    ErrorRaised(Torrent),
+    ErrorCleared(Torrent),
+    LabelsChanged { torrent: Torrent, old: Vec<String> },
+    Moved { torrent: Torrent, old: String },
+}
+
+impl TorrentEvent {
+    pub fn torrent(&self) -> &Torrent {
+        match self {
+            TorrentEvent::Added(torrent)
+            | TorrentEvent::Removed(torrent)
+            | TorrentEvent::Started(torrent)
+            | TorrentEvent::Stopped(torrent)
+            | TorrentEvent::Completed(torrent)
+            | TorrentEvent::Verified(torrent)
+            | TorrentEvent::ErrorRaised(torrent)
+            | TorrentEvent::ErrorCleared(torrent)
+            | TorrentEvent::LabelsChanged { torrent, .. }
+            | TorrentEvent::Moved { torrent, .. } => torrent,
+        }
+    }
+}
+
+fn is_checking(status: Option<TorrentStatus>) -> bool {
+    matches!(
+        status,
+        Some(TorrentStatus::CheckWait) | Some(TorrentStatus::Check)
+    )
+}
+
+fn status_of(torrent: &Torrent) -> Option<TorrentStatus> {
+    torrent
+        .status
+        .and_then(|status| TorrentStatus::try_from(status).ok())
+}
+
+// Events between two snapshots of the same torrent, fields missing from either side are ignored
+pub fn diff_torrent(old: &Torrent, new: &Torrent) -> Vec<TorrentEvent> {
+    let mut events = Vec::new();
+    let (old_status, new_status) = (status_of(old), status_of(new));
+    if let (Some(old_status), Some(new_status)) = (old_status, new_status) {
+        // Verifying a paused torrent passes through the check states without starting it
+        let checking = is_checking(Some(old_status)) || is_checking(Some(new_status));
+        if !checking && old_status == TorrentStatus::Stopped && new_status != TorrentStatus::Stopped
+        {
+            events.push(TorrentEvent::Started(new.clone()));
+        }
+        if !checking && old_status != TorrentStatus::Stopped && new_status == TorrentStatus::Stopped
+        {
+            events.push(TorrentEvent::Stopped(new.clone()));
+        }
+        if is_checking(Some(old_status)) && !is_checking(Some(new_status)) {
+            events.push(TorrentEvent::Verified(new.clone()));
+        }
+    }
+    if let (Some(old_done), Some(new_done)) = (old.percent_done, new.percent_done) {
+        if old_done < 1.0 && new_done >= 1.0 {
+            events.push(TorrentEvent::Completed(new.clone()));
+        }
+    }
+    if let (Some(old_error), Some(new_error)) = (old.error, new.error) {
+        if old_error == 0 && new_error != 0 {
+            events.push(TorrentEvent::ErrorRaised(new.clone()));
+        }
+        if old_error != 0 && new_error == 0 {
+            events.push(TorrentEvent::ErrorCleared(new.clone()));
+        }
+    }
+    if let (Some(old_labels), Some(new_labels)) = (&old.labels, &new.labels) {
+        if old_labels != new_labels {
+            events.push(TorrentEvent::LabelsChanged {
+                torrent: new.clone(),
+                old: old_labels.clone(),
+            });
+        }
+    }
+    if let (Some(old_dir), Some(new_dir)) = (&old.download_dir, &new.download_dir) {
+        if old_dir != new_dir {
+            events.push(TorrentEvent::Moved {
+                torrent: new.clone(),
+                old: old_dir.clone(),
+            });
+        }
+    }
+    events
+}
+
+pub struct Watcher {
+    client: Client,
+    interval: Duration,
+    fields: Vec<TorrentFields>,
+    emit_existing: bool,
+    known: Option<HashMap<i64, Torrent>>,
+    last_poll: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new(client: Client, interval: Duration) -> Self {
+        Watcher {
+            client,
+            interval,
+            fields: WATCHER_FIELDS.to_vec(),
+            emit_existing: false,
+            known: None,
+            last_poll: None,
+        }
+    }
+
+    // Extra fields to carry in the event snapshots
+    pub fn fields(mut self, fields: &[TorrentFields]) -> Self {
+        for field in fields {
+            if !self.fields.contains(field) {
+                self.fields.push(*field);
+            }
+        }
+        self
+    }
+
+    // Report the torrents found by the first poll as added
+    pub fn emit_existing(mut self, emit_existing: bool) -> Self {
+        self.emit_existing = emit_existing;
+        self
+    }
+
+    pub fn client(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+
+    // The first call loads every torrent, later calls only ask for the recently-active ones
+    // unless the last poll is too old for the daemon to still remember its changes
+    pub async fn poll(&mut self) -> Result<Vec<TorrentEvent>, Error> {
+        let started = Instant::now();
+        let full = match self.last_poll {
+            Some(last) => started - last >= RECENTLY_ACTIVE_WINDOW,
+            None => true,
+        };
+        let response = self
+            .client
+            .torrent_get(TorrentGetArgs {
+                ids: (!full).then_some(Ids::RecentlyActive),
+                fields: self.fields.clone(),
+            })
+            .await?;
+        self.last_poll = Some(started);
+        let mut events = Vec::new();
+        let known = match &mut self.known {
+            Some(known) => known,
+            None => {
+                let known = self.known.insert(HashMap::new());
+                for torrent in response.torrents {
+                    if let Some(id) = torrent.id {
+                        if self.emit_existing {
+                            events.push(TorrentEvent::Added(torrent.clone()));
+                        }
+                        known.insert(id, torrent);
+                    }
+                }
+                return Ok(events);
+            }
+        };
+        let mut removed = response.removed.unwrap_or_default();
+        if full {
+            // A full list has no removed ids, whatever is missing from it is gone
+            removed = known
+                .keys()
+                .filter(|id| {
+                    !response
+                        .torrents
+                        .iter()
+                        .any(|torrent| torrent.id == Some(**id))
+                })
+                .copied()
+                .collect();
+            removed.sort_unstable();
+        }
+        for torrent in response.torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            match known.get(&id) {
+                Some(old) => events.extend(diff_torrent(old, &torrent)),
+                None => events.push(TorrentEvent::Added(torrent.clone())),
+            }
+            known.insert(id, torrent);
+        }
+        for id in removed {
+            if let Some(torrent) = known.remove(&id) {
+                events.push(TorrentEvent::Removed(torrent));
+            }
+        }
+        Ok(events)
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<TorrentEvent, Error>> {
+        stream::unfold(
+            (self, VecDeque::new(), true),
+            |(mut watcher, mut pending, mut first)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((Ok(event), (watcher, pending, first)));
+                    }
+                    if !first {
+                        sleep(watcher.interval).await;
+                    }
+                    first = false;
+                    match watcher.poll().await {
+                        Ok(events) => pending.extend(events),
+                        Err(err) => return Some((Err(err), (watcher, pending, first))),
+                    }
+                }
+            },
+        )
+    }
+}
@@ -0,0 +1,213 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use common::{request_body, serve, temp_dir, Reply, HASH, TORRENT};
+use trpc::{
+    client::Client,
+    create::TorrentCreator,
+    magnet::MagnetLink,
+    metainfo::Metainfo,
+    torrent::{TorrentAdd, TorrentAddArgs},
+    Error,
+};
+
+#[test]
+fn test_add_args_from_bytes() {
+    let data = std::fs::read(TORRENT).unwrap();
+    let expected = Some(STANDARD.encode(&data));
+    assert_eq!(
+        TorrentAddArgs::from_bytes(&data).unwrap().metainfo,
+        expected
+    );
+    assert_eq!(
+        TorrentAddArgs::try_from(data.as_slice()).unwrap().metainfo,
+        expected
+    );
+    assert_eq!(
+        TorrentAddArgs::try_from(Bytes::from(data.clone()))
+            .unwrap()
+            .metainfo,
+        expected
+    );
+    assert_eq!(
+        TorrentAddArgs::from_reader(std::io::Cursor::new(&data))
+            .unwrap()
+            .metainfo,
+        expected
+    );
+    // The constructors pass any bytes on like from_meta, checking them is a separate call
+    let page = b"<html>not a torrent</html>";
+    assert_eq!(
+        TorrentAddArgs::from_bytes(page).unwrap().metainfo,
+        Some(STANDARD.encode(page))
+    );
+    assert!(Metainfo::from_bytes(page).is_err());
+}
+
+#[tokio::test]
+async fn test_add_args_from_async_reader() {
+    let file = tokio::fs::File::open(TORRENT).await.unwrap();
+    let args = TorrentAddArgs::from_async_reader(file).await.unwrap();
+    assert_eq!(
+        args.metainfo,
+        Some(STANDARD.encode(std::fs::read(TORRENT).unwrap()))
+    );
+}
+
+#[test]
+fn test_add_builder() {
+    let data = std::fs::read(TORRENT).unwrap();
+    let args = TorrentAddArgs::builder()
+        .metainfo(&data)
+        .download_dir("/data")
+        .paused(true)
+        .peer_limit(20)
+        .bandwidth_priority(1)
+        .labels(&["movies", "hd"])
+        .label("movies")
+        .bandwidth_group("slow")
+        .files_unwanted(&[1])
+        .priority_high(&[0])
+        .build()
+        .unwrap();
+    assert_eq!(args.metainfo, Some(STANDARD.encode(&data)));
+    assert_eq!(
+        args.labels,
+        Some(vec!["movies".to_string(), "hd".to_string()])
+    );
+    assert_eq!(args.files_unwanted, Some(vec![1]));
+    assert_eq!(args.files_wanted, None);
+    let json = serde_json::to_value(&args).unwrap();
+    assert_eq!(json["download-dir"], "/data");
+    assert_eq!(json["peer-limit"], 20);
+    assert_eq!(json["bandwidthPriority"], 1);
+    assert_eq!(json["priority-high"], serde_json::json!([0]));
+    assert!(json.get("bandwidth_group").is_none());
+    assert!(json.get("group").is_none());
+    assert!(json.get("filename").is_none());
+
+    let magnet: MagnetLink = "magnet:?xt=urn:btih:6a0a9282c65fc6a1324e6e1605fe9bb9746c3aa8"
+        .parse()
+        .unwrap();
+    let args = TorrentAddArgs::builder().magnet(&magnet).build().unwrap();
+    assert_eq!(args.filename, Some(magnet.to_string()));
+}
+
+#[test]
+fn test_add_builder_validation() {
+    let data = std::fs::read(TORRENT).unwrap();
+    let builder = || TorrentAddArgs::builder().metainfo(&data);
+    assert!(matches!(
+        builder().filename("http://a/b.torrent").build(),
+        Err(Error::BothFileMeta)
+    ));
+    assert!(matches!(
+        TorrentAddArgs::builder().paused(true).build(),
+        Err(Error::NoFileMeta)
+    ));
+    assert!(builder().peer_limit(0).build().is_err());
+    assert!(builder().bandwidth_priority(2).build().is_err());
+    assert!(builder().label("a,b").build().is_err());
+    assert!(builder()
+        .files_wanted(&[0])
+        .files_unwanted(&[0])
+        .build()
+        .is_err());
+    assert!(builder().priority_low(&[5]).build().is_err());
+    assert!(TorrentAddArgs::builder()
+        .filename("magnet:?xt=urn:btih:aa")
+        .priority_low(&[5])
+        .build()
+        .is_ok());
+    assert!(TorrentAddArgs::builder()
+        .metainfo(b"not a torrent")
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_add_builder_skips_pad_files() {
+    let dir = temp_dir("add-pad");
+    std::fs::write(dir.join("a.bin"), vec![1u8; 20000]).unwrap();
+    std::fs::write(dir.join("b.bin"), vec![2u8; 20000]).unwrap();
+    let data = TorrentCreator::new(&dir)
+        .piece_length(16384)
+        .hybrid(true)
+        .create()
+        .unwrap();
+    let builder = || TorrentAddArgs::builder().metainfo(&data);
+    assert!(builder().files_unwanted(&[1]).build().is_ok());
+    assert!(builder().files_unwanted(&[2]).build().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_builder_add_keeps_added_when_group_fails() {
+    let (base, requests) = serve(|request| {
+        let body = if request.contains("torrent-set") {
+            r#"{"result":"unknown group"}"#.to_string()
+        } else {
+            format!(
+                r#"{{"result":"success","arguments":{{"torrent-added":{{"hashString":"{}","id":7,"name":"test dir"}}}}}}"#,
+                HASH
+            )
+        };
+        Reply::ok(body.into_bytes())
+    })
+    .await;
+    let builder = || {
+        TorrentAddArgs::builder()
+            .metainfo(&std::fs::read(TORRENT).unwrap())
+            .bandwidth_group("slow")
+    };
+    let mut client = Client::new(&format!("{}/transmission/rpc", base));
+
+    // torrent_add alone sends one request and no group
+    client
+        .torrent_add(builder().build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    let outcome = builder().add(&mut client).await.unwrap();
+    assert!(!outcome.added.is_duplicate());
+    assert_eq!(outcome.added.id(), 7);
+    assert!(matches!(outcome.group, Some(Err(Error::BadResponse(_)))));
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    let set = request_body(&requests[2]);
+    assert_eq!(set["method"], "torrent-set");
+    assert_eq!(set["arguments"]["group"], "slow");
+    assert_eq!(set["arguments"]["ids"], 7);
+}
+
+#[test]
+fn test_torrent_add_result() {
+    let added: TorrentAdd = serde_json::from_value(serde_json::json!({
+        "torrent-added": { "hashString": "aa", "id": 3, "name": "new" }
+    }))
+    .unwrap();
+    assert!(!added.is_duplicate());
+    assert_eq!(added.id(), 3);
+    let duplicate: TorrentAdd = serde_json::from_value(serde_json::json!({
+        "torrent-duplicate": { "hashString": "bb", "id": 1, "name": "old" }
+    }))
+    .unwrap();
+    assert!(duplicate.is_duplicate());
+    assert_eq!(duplicate.into_response().name, "old");
+}
+
+#[test]
+fn test_add_args_trackers() {
+    let magnet = TorrentAddArgs::from_file(
+        "magnet:?xt=urn:btih:6a0a9282c65fc6a1324e6e1605fe9bb9746c3aa8&tr=http://a/ann&tr=http://b/ann",
+    )
+    .unwrap();
+    assert_eq!(
+        magnet.trackers().tiers,
+        vec![vec!["http://a/ann"], vec!["http://b/ann"]]
+    );
+    let meta = TorrentAddArgs::from_meta(TORRENT).unwrap();
+    assert!(meta.trackers().is_empty());
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{serve_rpc, temp_dir, HASH, TORRENT};
+use trpc::{
+    bulk::{BulkAdd, BulkOutcome, BulkSource},
+    client::Client,
+    torrent::TorrentAddArgs,
+    Error,
+};
+
+const OTHER: &str = "0123456789abcdef0123456789abcdef01234567";
+
+// Answers torrent-duplicate for requests mentioning HASH and torrent-added otherwise
+async fn serve() -> String {
+    serve_rpc(|request| {
+        if request.contains(HASH) {
+            "torrent-duplicate"
+        } else {
+            "torrent-added"
+        }
+    })
+    .await
+}
+
+#[test]
+fn test_sources() {
+    let dir = temp_dir("bulk-sources");
+    std::fs::write(dir.join("b.torrent"), b"").unwrap();
+    std::fs::write(dir.join("a.TORRENT"), b"").unwrap();
+    std::fs::write(dir.join("c.txt"), b"").unwrap();
+    let magnets = dir.join("magnets.txt");
+    std::fs::write(
+        &magnets,
+        "# old tracker\n\nmagnet:?xt=urn:btih:1\r\n  magnet:?xt=urn:btih:2 \n",
+    )
+    .unwrap();
+
+    let bulk = BulkAdd::new()
+        .magnet_file(&magnets)
+        .unwrap()
+        .torrent_dir(&dir)
+        .unwrap();
+    assert_eq!(
+        bulk.sources(),
+        &[
+            BulkSource::Magnet("magnet:?xt=urn:btih:1".to_string()),
+            BulkSource::Magnet("magnet:?xt=urn:btih:2".to_string()),
+            BulkSource::File(dir.join("a.TORRENT")),
+            BulkSource::File(dir.join("b.torrent")),
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_bulk_add_report() {
+    let bulk = BulkAdd::new()
+        .magnets(&format!(
+            "magnet:?xt=urn:btih:{}\nmagnet:?xt=urn:btih:short\n",
+            OTHER
+        ))
+        .source(BulkSource::File(PathBuf::from(TORRENT)))
+        .source(BulkSource::File(PathBuf::from("tests/missing.torrent")))
+        .template(TorrentAddArgs {
+            paused: Some(true),
+            ..TorrentAddArgs::default()
+        })
+        .concurrency(2);
+    let client = Client::new(&serve().await);
+    let report = client.bulk_add(&bulk).await;
+
+    assert_eq!(report.items.len(), 4);
+    assert!(matches!(report.items[0].outcome, BulkOutcome::Added(_)));
+    assert!(matches!(
+        report.items[1].outcome,
+        BulkOutcome::Failed(Error::WrongMagnet(_))
+    ));
+    assert!(matches!(report.items[2].outcome, BulkOutcome::Added(_)));
+    assert!(matches!(
+        report.items[3].outcome,
+        BulkOutcome::Failed(Error::Io(_))
+    ));
+    assert_eq!(report.added().count(), 2);
+    assert_eq!(report.failed().count(), 2);
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["added"], 2);
+    assert_eq!(json["duplicate"], 0);
+    assert_eq!(json["failed"], 2);
+    assert_eq!(json["items"][0]["status"], "added");
+    assert_eq!(json["items"][0]["id"], 7);
+    assert_eq!(json["items"][1]["status"], "failed");
+    assert_eq!(json["items"][1]["retry"], false);
+    assert_eq!(json["items"][2]["source"], TORRENT);
+    assert_eq!(json["items"][3]["retry"], false);
+}
+
+#[tokio::test]
+async fn test_bulk_add_duplicate() {
+    let bulk = BulkAdd::new().magnets(&format!("magnet:?xt=urn:btih:{}", HASH));
+    let report = Client::new(&serve().await).bulk_add(&bulk).await;
+    assert_eq!(report.duplicates().count(), 1);
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["items"][0]["status"], "duplicate");
+    assert!(json["items"][0].get("error").is_none());
+}
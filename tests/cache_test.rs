@@ -0,0 +1,109 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::{request_body, serve_json};
+use trpc::{
+    cache::{merge_torrent, TorrentCache},
+    client::Client,
+    request::Id,
+    torrent::{Torrent, TorrentFields, TorrentGet},
+};
+
+fn torrent(id: i64, hash: &str, name: &str) -> Torrent {
+    Torrent {
+        id: Some(id),
+        hash_string: Some(hash.to_string()),
+        name: Some(name.to_string()),
+        rate_download: Some(0),
+        ..Torrent::default()
+    }
+}
+
+#[test]
+fn test_merge_torrent_keeps_missing_fields() {
+    let mut current = torrent(1, "aa", "first");
+    let update = Torrent {
+        id: Some(1),
+        rate_download: Some(512),
+        ..Torrent::default()
+    };
+    merge_torrent(&mut current, update).unwrap();
+    assert_eq!(current.name.as_deref(), Some("first"));
+    assert_eq!(current.rate_download, Some(512));
+}
+
+#[test]
+fn test_cache_apply_update_and_removed() {
+    let cache = TorrentCache::new();
+    assert!(!cache.is_loaded());
+    cache.replace_all(vec![torrent(1, "AA", "first"), torrent(2, "bb", "second")]);
+    let reader = cache.clone();
+    assert_eq!(reader.len(), 2);
+    assert_eq!(reader.get_by_hash("aa").unwrap().id, Some(1));
+    let before = reader.generation();
+
+    cache
+        .apply(TorrentGet {
+            torrents: vec![
+                Torrent {
+                    id: Some(2),
+                    rate_download: Some(100),
+                    ..Torrent::default()
+                },
+                torrent(3, "cc", "third"),
+            ],
+            removed: Some(vec![1]),
+        })
+        .unwrap();
+
+    assert!(reader.generation() > before);
+    assert!(reader.get(1).is_none());
+    assert!(reader.get_by_hash("AA").is_none());
+    let second = reader.get_by_id(&Id::Hash("bb".to_string())).unwrap();
+    assert_eq!(second.name.as_deref(), Some("second"));
+    assert_eq!(second.rate_download, Some(100));
+    let ids: Vec<Option<i64>> = reader.snapshot().iter().map(|torrent| torrent.id).collect();
+    assert_eq!(ids, vec![Some(2), Some(3)]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_cache_update_reloads_after_window() {
+    let polls = AtomicUsize::new(0);
+    let (url, requests) = serve_json(move |_| {
+        let torrents = match polls.fetch_add(1, Ordering::SeqCst) {
+            0 => r#"{"torrents":[{"id":1,"hashString":"aa"},{"id":2,"hashString":"bb"}]}"#,
+            _ => r#"{"torrents":[{"id":2,"hashString":"bb","name":"second"}]}"#,
+        };
+        torrents.to_string()
+    })
+    .await;
+    let mut client = Client::new(&url);
+    let cache = TorrentCache::new();
+    let fields = [TorrentFields::Name];
+    cache.update(&mut client, &fields).await.unwrap();
+    cache.update(&mut client, &fields).await.unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // Torrent 1 was removed too long ago to be listed as removed, only a full load drops it
+    tokio::time::advance(Duration::from_secs(120)).await;
+    cache.update(&mut client, &fields).await.unwrap();
+    assert!(cache.get(1).is_none());
+    assert_eq!(cache.get(2).unwrap().name.as_deref(), Some("second"));
+
+    let ids: Vec<_> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request_body(request)["arguments"]["ids"].clone())
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            serde_json::Value::Null,
+            serde_json::json!("recently-active"),
+            serde_json::Value::Null
+        ]
+    );
+}
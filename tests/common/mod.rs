@@ -0,0 +1,161 @@
+// Helpers shared by the integration tests, not every test file uses all of them
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub const TORRENT: &str = "tests/test dir.torrent";
+pub const HASH: &str = "6a0a9282c65fc6a1324e6e1605fe9bb9746c3aa8";
+
+// An empty directory under the system temp dir, unique per test process
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trpc-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub struct Reply {
+    pub status: &'static str,
+    pub headers: String,
+    pub body: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(body: Vec<u8>) -> Self {
+        Reply {
+            status: "200 OK",
+            headers: String::new(),
+            body,
+        }
+    }
+
+    pub fn status(status: &'static str) -> Self {
+        Reply {
+            status,
+            headers: String::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Reply {
+            status: "302 Found",
+            headers: format!("Location: {}\r\n", location),
+            body: Vec::new(),
+        }
+    }
+}
+
+// The request head and body as text
+async fn read_request(stream: &mut TcpStream) -> String {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+        let request = String::from_utf8_lossy(&buf).to_lowercase();
+        if let Some(end) = request.find("\r\n\r\n") {
+            let length = request
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|length| length.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if buf.len() >= end + 4 + length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&buf).to_string()
+}
+
+// An http server on a free local port answering every request with handler.
+// Returns its base url and every request it got.
+pub async fn serve<F>(handler: F) -> (String, Arc<Mutex<Vec<String>>>)
+where
+    F: Fn(&str) -> Reply + Send + Sync + 'static,
+{
+    serve_at("127.0.0.1", handler).await
+}
+
+// Like serve on another loopback address, to test requests crossing hosts
+pub async fn serve_at<F>(host: &str, handler: F) -> (String, Arc<Mutex<Vec<String>>>)
+where
+    F: Fn(&str) -> Reply + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind((host, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let seen = seen.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut stream).await;
+                seen.lock().unwrap().push(request.clone());
+                let reply = handler(&request);
+                let head = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    reply.status,
+                    reply.headers,
+                    reply.body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&reply.body).await.unwrap();
+                stream.shutdown().await.unwrap();
+            });
+        }
+    });
+    (format!("http://{}", addr), requests)
+}
+
+// The path of the request line
+pub fn request_path(request: &str) -> &str {
+    request.split(' ').nth(1).unwrap_or("")
+}
+
+// A fake daemon answering every request with the arguments handler returns for it.
+// Returns the rpc url and every request it got.
+pub async fn serve_json<F>(handler: F) -> (String, Arc<Mutex<Vec<String>>>)
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    let (base, requests) = serve(move |request| {
+        let body = format!(r#"{{"result":"success","arguments":{}}}"#, handler(request));
+        Reply::ok(body.into_bytes())
+    })
+    .await;
+    (format!("{}/transmission/rpc", base), requests)
+}
+
+// The json body of a request
+pub fn request_body(request: &str) -> serde_json::Value {
+    let body = request.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    serde_json::from_str(body).unwrap()
+}
+
+// A fake daemon answering every torrent-add with the key handler picks for the request,
+// torrent-added or torrent-duplicate
+pub async fn serve_rpc<F>(handler: F) -> String
+where
+    F: Fn(&str) -> &'static str + Send + Sync + 'static,
+{
+    let (url, _) = serve_json(move |request| {
+        format!(
+            r#"{{"{}":{{"hashString":"{}","id":7,"name":"test dir"}}}}"#,
+            handler(request),
+            HASH
+        )
+    })
+    .await;
+    url
+}
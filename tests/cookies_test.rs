@@ -0,0 +1,91 @@
+mod common;
+
+use common::{Reply, TORRENT};
+use trpc::{cookies::CookieJar, fetch::TorrentFetch, torrent::TorrentAddArgs};
+
+const NOW: i64 = 1_700_000_000;
+
+const COOKIES: &str = "# Netscape HTTP Cookie File
+# exported by a browser
+
+.tracker.org\tTRUE\t/\tFALSE\t0\tuid\t42
+.tracker.org\tTRUE\t/download\tTRUE\t2000000000\tpass\tsecret
+#HttpOnly_tracker.org\tFALSE\t/\tFALSE\t2000000000\tsession\tabc
+.tracker.org\tTRUE\t/\tFALSE\t1600000000\told\texpired
+other.org\tFALSE\t/\tFALSE\t0\tforeign\tvalue
+.tracker.org\tTRUE\t/\tFALSE\t0\tempty\t
+";
+
+#[test]
+fn test_cookie_jar_parse() {
+    let jar = CookieJar::parse(COOKIES).unwrap();
+    assert_eq!(jar.cookies().len(), 6);
+    let session = &jar.cookies()[2];
+    assert!(session.http_only);
+    assert_eq!(session.domain, "tracker.org");
+    assert_eq!(jar.cookies()[5].value, "");
+    assert!(CookieJar::parse("tracker.org\tTRUE\t/\n").is_err());
+    assert!(CookieJar::parse("tracker.org\tYES\t/\tFALSE\t0\ta\tb\n").is_err());
+}
+
+#[test]
+fn test_cookie_jar_matching() {
+    let jar = CookieJar::parse(COOKIES).unwrap();
+    assert_eq!(
+        jar.header_at("https://www.tracker.org/download/1.torrent?key=x", NOW)
+            .as_deref(),
+        Some("pass=secret; uid=42; empty=")
+    );
+    // Secure cookie needs https, host-only cookie needs the exact host
+    assert_eq!(
+        jar.header_at("http://www.tracker.org/download/1.torrent", NOW)
+            .as_deref(),
+        Some("uid=42; empty=")
+    );
+    assert_eq!(
+        jar.header_at("http://tracker.org:8080/downloads", NOW)
+            .as_deref(),
+        Some("uid=42; session=abc; empty=")
+    );
+    assert_eq!(jar.header_at("http://nottracker.org/", NOW), None);
+    assert_eq!(
+        jar.header_at("https://tracker.org/download", 2_100_000_000)
+            .as_deref(),
+        Some("uid=42; empty=")
+    );
+}
+
+#[test]
+fn test_cookie_jar_fills_add_args() {
+    let jar = CookieJar::parse(COOKIES).unwrap();
+    let args = TorrentAddArgs::from_file("http://other.org/a.torrent")
+        .unwrap()
+        .cookies_from_jar(&jar);
+    assert_eq!(args.cookies.as_deref(), Some("foreign=value"));
+    let args = TorrentAddArgs::from_file("http://unknown.org/a.torrent")
+        .unwrap()
+        .cookies_from_jar(&jar);
+    assert_eq!(args.cookies, None);
+}
+
+#[tokio::test]
+async fn test_fetch_picks_jar_cookies_per_redirect() {
+    let (other, other_requests) =
+        common::serve_at("127.0.0.2", |_| Reply::ok(std::fs::read(TORRENT).unwrap())).await;
+    let target = format!("{}/file.torrent", other);
+    let (base, requests) = common::serve(move |_| Reply::redirect(&target)).await;
+    let jar = CookieJar::parse(
+        "127.0.0.1\tFALSE\t/\tFALSE\t0\tfirst\t1\n127.0.0.2\tFALSE\t/\tFALSE\t0\tsecond\t2\n",
+    )
+    .unwrap();
+    TorrentFetch::new(&format!("{}/old", base))
+        .cookie_jar(&jar)
+        .fetch()
+        .await
+        .unwrap();
+    let first = requests.lock().unwrap()[0].to_lowercase();
+    assert!(first.contains("cookie: first=1\r\n"));
+    let second = other_requests.lock().unwrap()[0].to_lowercase();
+    assert!(second.contains("cookie: second=2\r\n"));
+    assert!(!second.contains("first=1"));
+}
@@ -0,0 +1,221 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use trpc::{bencode::decode, create::TorrentCreator, metainfo::Metainfo};
+
+mod common;
+
+const TEST_DIR: &str = "tests/test dir";
+
+#[test]
+fn test_create_matches_fixture() {
+    let data = TorrentCreator::new(TEST_DIR)
+        .piece_length(32768)
+        .comment("test torrent")
+        .created_by(Some("Transmission/3.00 (bb6b5a062e)"))
+        .creation_date(Some(1622530745))
+        .create()
+        .unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    let fixture = Metainfo::from_file(common::TORRENT).unwrap();
+    assert_eq!(metainfo.info_hash(), fixture.info_hash());
+    assert_eq!(metainfo.file_paths(), fixture.file_paths());
+    assert_eq!(metainfo.comment, fixture.comment);
+}
+
+#[test]
+fn test_create_options() {
+    let data = TorrentCreator::new(TEST_DIR)
+        .name("dataset")
+        .tracker_tier(&["http://a/announce", "http://b/announce"])
+        .tracker("http://c/announce")
+        .web_seed("http://seed/")
+        .private(true)
+        .source("internal")
+        .created_by(None)
+        .creation_date(None)
+        .create()
+        .unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    assert_eq!(metainfo.name, "dataset");
+    assert_eq!(metainfo.piece_length, 16384);
+    assert_eq!(
+        metainfo.trackers,
+        vec![
+            vec!["http://a/announce", "http://b/announce"],
+            vec!["http://c/announce"]
+        ]
+    );
+    assert_eq!(metainfo.web_seeds, vec!["http://seed/"]);
+    assert!(metainfo.private);
+    assert_eq!(metainfo.source.as_deref(), Some("internal"));
+    assert_eq!(metainfo.created_by, None);
+    assert_eq!(metainfo.creation_date, None);
+}
+
+#[test]
+fn test_create_rejects_bad_piece_length() {
+    assert!(TorrentCreator::new(TEST_DIR)
+        .piece_length(20000)
+        .create()
+        .is_err());
+    assert!(TorrentCreator::new(TEST_DIR)
+        .piece_length(8192)
+        .create()
+        .is_err());
+    assert!(TorrentCreator::new("tests/missing dir").create().is_err());
+}
+
+#[test]
+fn test_create_hybrid() {
+    let dir = common::temp_dir("create");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    let big: Vec<u8> = (0..40000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(dir.join("a.txt"), b"hello").unwrap();
+    std::fs::write(dir.join("sub").join("big.bin"), &big).unwrap();
+    std::fs::write(dir.join("empty"), b"").unwrap();
+
+    let creator = TorrentCreator::new(&dir).piece_length(16384).hybrid(true);
+    let data = creator.create().unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    assert!(metainfo.has_v1());
+    assert!(metainfo.has_v2());
+    assert_eq!(metainfo.info_hash_v2().unwrap().len(), 64);
+    // a.txt gets padded to a piece, the empty file needs no padding, big.bin takes 3 pieces
+    assert_eq!(metainfo.piece_count(), 4);
+    assert_eq!(metainfo.files.iter().filter(|file| file.padding).count(), 1);
+    let name = metainfo.name.clone();
+    assert_eq!(
+        metainfo.file_paths(),
+        vec![
+            format!("{}/a.txt", name),
+            format!("{}/empty", name),
+            format!("{}/sub/big.bin", name)
+        ]
+    );
+
+    let root = decode(&data).unwrap();
+    let tree = root.get("info").unwrap().get("file tree").unwrap();
+    let big_leaf = tree
+        .get("sub")
+        .unwrap()
+        .get("big.bin")
+        .unwrap()
+        .get("")
+        .unwrap();
+    let pieces_root = big_leaf.get("pieces root").unwrap().as_bytes().unwrap();
+    let layer = root
+        .get("piece layers")
+        .unwrap()
+        .as_dict()
+        .unwrap()
+        .get(pieces_root)
+        .unwrap();
+    assert_eq!(layer.as_bytes().unwrap().len(), 3 * 32);
+    let empty_leaf = tree.get("empty").unwrap().get("").unwrap();
+    assert!(empty_leaf.get("pieces root").is_none());
+
+    let args = creator.add_args().unwrap();
+    assert_eq!(STANDARD.decode(args.metainfo.unwrap()).unwrap(), data);
+    assert_eq!(
+        args.download_dir.as_deref(),
+        std::fs::canonicalize(std::env::temp_dir())
+            .unwrap()
+            .to_str()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Expected values were computed separately from BEP 52: sha256 over 16 KiB blocks,
+// pieces padded with zero hashes and the root padded with empty piece hashes
+#[test]
+fn test_create_v2_known_answer() {
+    let dir = common::temp_dir("create-kat");
+    let file = dir.join("kat.bin");
+    let data: Vec<u8> = (0..80000u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&file, &data).unwrap();
+
+    let data = TorrentCreator::new(&file)
+        .piece_length(32768)
+        .created_by(None)
+        .creation_date(None)
+        .hybrid(true)
+        .create()
+        .unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    assert_eq!(
+        metainfo.info_hash_v1().as_deref(),
+        Some("1b5c7901c342853e764213398c018bee83342896")
+    );
+    assert_eq!(
+        metainfo.info_hash_v2().as_deref(),
+        Some("c00112c54824bbcc9a8c5451ce321e915031ef56b6e8cda8a721f341b6ba16cd")
+    );
+
+    let root = decode(&data).unwrap();
+    let leaf = root
+        .get("info")
+        .unwrap()
+        .get("file tree")
+        .unwrap()
+        .get("kat.bin")
+        .unwrap()
+        .get("")
+        .unwrap();
+    let pieces_root = leaf.get("pieces root").unwrap().as_bytes().unwrap();
+    assert_eq!(
+        hex(pieces_root),
+        "154573bf8a587dacfdac657aa44942dce46e631a7ec6bddd8c00555d24dd1dd7"
+    );
+    let layer = root
+        .get("piece layers")
+        .unwrap()
+        .as_dict()
+        .unwrap()
+        .get(pieces_root)
+        .unwrap();
+    assert_eq!(
+        hex(layer.as_bytes().unwrap()),
+        concat!(
+            "d9e13d0b676ad681164ef0b7b5910d1328ea83a047cad57e619d76bbe3a08525",
+            "e28097eaaa55956702cf8195d1a551dbabb63e3d679b294cf33d506a6b5ef479",
+            "d689d61943dd92ceb951c9e873a184d24277d611a77b5c0b495dbf3d90e6cbf0"
+        )
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_create_skips_symlinks() {
+    let dir = common::temp_dir("create-links");
+    let data_dir = dir.join("data");
+    std::fs::create_dir_all(data_dir.join("sub")).unwrap();
+    std::fs::write(data_dir.join("a.txt"), b"hello").unwrap();
+    std::fs::write(dir.join("outside.txt"), b"secret").unwrap();
+    std::os::unix::fs::symlink("..", data_dir.join("sub").join("loop")).unwrap();
+    std::os::unix::fs::symlink(dir.join("outside.txt"), data_dir.join("outside.txt")).unwrap();
+
+    let data = TorrentCreator::new(&data_dir).create().unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    assert_eq!(metainfo.file_paths(), vec!["data/a.txt"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_add_args_needs_name_on_disk() {
+    assert!(TorrentCreator::new(TEST_DIR)
+        .name("renamed")
+        .add_args()
+        .is_err());
+    let args = TorrentCreator::new(TEST_DIR)
+        .name("test dir")
+        .add_args()
+        .unwrap();
+    assert_eq!(
+        args.download_dir.as_deref(),
+        std::fs::canonicalize("tests").unwrap().to_str()
+    );
+}
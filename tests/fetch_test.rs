@@ -0,0 +1,115 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{request_path, Reply, TORRENT};
+use tokio::net::TcpListener;
+use trpc::{fetch::TorrentFetch, torrent::TorrentAddArgs, Error};
+
+// Serves /file.torrent, redirects /old to it, answers 404 elsewhere and records every request
+async fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    common::serve(|request| match request_path(request) {
+        "/file.torrent" => Reply::ok(std::fs::read(TORRENT).unwrap()),
+        "/old" => Reply::redirect("/file.torrent"),
+        "/loop" => Reply::redirect("/loop"),
+        "/page" => Reply::ok(b"<html></html>".to_vec()),
+        _ => Reply::status("404 Not Found"),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_fetch_with_cookies_and_headers() {
+    let (base, requests) = serve().await;
+    let data = TorrentFetch::new(&format!("{}/file.torrent", base))
+        .cookies("uid=1; pass=secret")
+        .header("X-Api-Key", "key")
+        .fetch()
+        .await
+        .unwrap();
+    assert_eq!(data, std::fs::read(TORRENT).unwrap());
+    let request = requests.lock().unwrap()[0].to_lowercase();
+    assert!(request.contains("cookie: uid=1; pass=secret"));
+    assert!(request.contains("x-api-key: key"));
+}
+
+#[tokio::test]
+async fn test_fetch_follows_redirects() {
+    let (base, requests) = serve().await;
+    let metainfo = TorrentFetch::new(&format!("{}/old", base))
+        .fetch_metainfo()
+        .await
+        .unwrap();
+    assert_eq!(metainfo.name, "test dir");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_redirect_to_other_origin_drops_credentials() {
+    let (other, other_requests) =
+        common::serve(|_| Reply::ok(std::fs::read(TORRENT).unwrap())).await;
+    let target = format!("{}/file.torrent", other);
+    let (base, requests) = common::serve(move |_| Reply::redirect(&target)).await;
+    let data = TorrentFetch::new(&format!("{}/old", base))
+        .cookies("uid=1; pass=secret")
+        .header("X-Api-Key", "key")
+        .fetch()
+        .await
+        .unwrap();
+    assert_eq!(data, std::fs::read(TORRENT).unwrap());
+    assert!(requests.lock().unwrap()[0]
+        .to_lowercase()
+        .contains("cookie: uid=1"));
+    let other_requests = other_requests.lock().unwrap();
+    assert_eq!(other_requests.len(), 1);
+    let request = other_requests[0].to_lowercase();
+    assert!(!request.contains("cookie"));
+    assert!(!request.contains("x-api-key"));
+}
+
+#[tokio::test]
+async fn test_fetch_limits() {
+    let (base, requests) = serve().await;
+    let looping = TorrentFetch::new(&format!("{}/loop", base))
+        .max_redirects(3)
+        .fetch()
+        .await;
+    assert!(matches!(looping, Err(Error::FetchRedirects(3))));
+    assert_eq!(requests.lock().unwrap().len(), 4);
+    let large = TorrentFetch::new(&format!("{}/file.torrent", base))
+        .max_size(100)
+        .fetch()
+        .await;
+    assert!(matches!(large, Err(Error::FetchTooLarge(100))));
+}
+
+#[tokio::test]
+async fn test_fetch_errors() {
+    let (base, _) = serve().await;
+    let missing = TorrentFetch::new(&format!("{}/missing", base))
+        .fetch()
+        .await;
+    assert!(matches!(missing, Err(Error::FetchStatus(404))));
+    let page = TorrentFetch::new(&format!("{}/page", base)).fetch().await;
+    assert!(matches!(page, Err(Error::Bencode(_))));
+
+    // A listener that never answers
+    let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/file.torrent", silent.local_addr().unwrap());
+    let slow = TorrentFetch::new(&url)
+        .timeout(Duration::from_millis(200))
+        .fetch()
+        .await;
+    assert!(matches!(slow, Err(Error::FetchTimeout)));
+}
+
+#[test]
+fn test_remote_fetch_from_add_args() {
+    let mut args = TorrentAddArgs::from_file("http://tracker/file.torrent").unwrap();
+    args.cookies = Some("uid=1".to_string());
+    let fetch = args.remote_fetch().unwrap();
+    assert_eq!(fetch.url(), "http://tracker/file.torrent");
+    let magnet = TorrentAddArgs::from_file("magnet:?xt=urn:btih:aa").unwrap();
+    assert!(magnet.remote_fetch().is_none());
+}
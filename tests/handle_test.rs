@@ -0,0 +1,147 @@
+mod common;
+
+use common::{request_body, serve_json, HASH};
+use serde_json::{json, Value};
+use trpc::{client::Client, torrent::TorrentFields};
+
+// A daemon knowing torrent 5, labelled "a"
+async fn serve() -> (String, impl Fn() -> Vec<Value>) {
+    let (url, requests) = serve_json(|request| {
+        match request_body(request)["method"].as_str() {
+            Some("torrent-get") => {
+                json!({ "torrents": [{ "id": 5, "name": "t", "labels": ["a"] }] })
+            }
+            Some("torrent-rename-path") => json!({ "id": 5, "name": "b", "path": "a" }),
+            _ => json!({}),
+        }
+        .to_string()
+    })
+    .await;
+    let bodies = move || {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request_body(request))
+            .collect()
+    };
+    (url, bodies)
+}
+
+#[tokio::test]
+async fn test_handle_actions_send_ids() {
+    let (url, bodies) = serve().await;
+    let mut client = Client::new(&url);
+    let mut handle = client.torrent(HASH);
+    handle.start().await.unwrap();
+    handle.start_now().await.unwrap();
+    handle.stop().await.unwrap();
+    handle.verify().await.unwrap();
+    handle.reannounce().await.unwrap();
+    handle.queue_move_top().await.unwrap();
+    handle.queue_move_up().await.unwrap();
+    handle.queue_move_down().await.unwrap();
+    handle.queue_move_bottom().await.unwrap();
+
+    let bodies = bodies();
+    let methods: Vec<&str> = bodies
+        .iter()
+        .map(|body| body["method"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        methods,
+        vec![
+            "torrent-start",
+            "torrent-start-now",
+            "torrent-stop",
+            "torrent-verify",
+            "torrent-reannounce",
+            "queue-move-top",
+            "queue-move-up",
+            "queue-move-down",
+            "queue-move-bottom",
+        ]
+    );
+    for body in &bodies {
+        assert_eq!(body["arguments"], json!({ "ids": [HASH] }));
+    }
+}
+
+#[tokio::test]
+async fn test_handle_location_rename_remove() {
+    let (url, bodies) = serve().await;
+    let mut client = Client::new(&url);
+    let mut handle = client.torrent(5);
+    handle.set_location("/data", true).await.unwrap();
+    let renamed = handle.rename("a", "b").await.unwrap();
+    assert_eq!(renamed.name, "b");
+    handle.remove(false).await.unwrap();
+
+    let bodies = bodies();
+    assert_eq!(bodies[0]["method"], "torrent-set-location");
+    assert_eq!(
+        bodies[0]["arguments"],
+        json!({ "ids": 5, "location": "/data", "move": true })
+    );
+    assert_eq!(bodies[1]["method"], "torrent-rename-path");
+    assert_eq!(
+        bodies[1]["arguments"],
+        json!({ "ids": 5, "path": "a", "name": "b" })
+    );
+    assert_eq!(bodies[2]["method"], "torrent-remove");
+    assert_eq!(
+        bodies[2]["arguments"],
+        json!({ "ids": 5, "delete-local-data": false })
+    );
+}
+
+#[tokio::test]
+async fn test_handle_settings() {
+    let (url, bodies) = serve().await;
+    let mut client = Client::new(&url);
+    let mut handle = client.torrent(5);
+    handle.set_limits(Some(100), None).await.unwrap();
+    handle.set_seed_ratio_limit(None).await.unwrap();
+    handle.set_bandwidth_priority(1).await.unwrap();
+
+    let bodies = bodies();
+    assert!(bodies.iter().all(|body| body["method"] == "torrent-set"));
+    assert_eq!(
+        bodies[0]["arguments"],
+        json!({
+            "ids": 5,
+            "downloadLimit": 100,
+            "downloadLimited": true,
+            "uploadLimited": false,
+        })
+    );
+    assert_eq!(
+        bodies[1]["arguments"],
+        json!({ "ids": 5, "seedRatioMode": 2 })
+    );
+    assert_eq!(
+        bodies[2]["arguments"],
+        json!({ "ids": 5, "bandwidthPriority": 1 })
+    );
+}
+
+#[tokio::test]
+async fn test_handle_refresh_and_labels() {
+    let (url, bodies) = serve().await;
+    let mut client = Client::new(&url);
+    let mut handle = client.torrent(5);
+    let torrent = handle.refresh(vec![TorrentFields::Name]).await.unwrap();
+    assert_eq!(torrent.name.as_deref(), Some("t"));
+    assert!(handle.add_labels(&["b"]).await.unwrap());
+
+    let bodies = bodies();
+    assert_eq!(bodies[0]["method"], "torrent-get");
+    assert_eq!(
+        bodies[0]["arguments"],
+        json!({ "ids": 5, "fields": ["name"] })
+    );
+    let set = bodies.last().unwrap();
+    assert_eq!(set["method"], "torrent-set");
+    assert_eq!(set["arguments"]["ids"], json!([5]));
+    assert_eq!(set["arguments"]["labels"], json!(["a", "b"]));
+}
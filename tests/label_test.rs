@@ -0,0 +1,98 @@
+use trpc::{
+    client::Client,
+    label::{
+        check_label, check_namespace_key, merge_labels, rename_label, set_namespace, strip_labels,
+        LabelNamespaces, NamespaceFilter,
+    },
+    request::Ids,
+    Error,
+};
+
+fn labels(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn test_check_label() {
+    assert!(check_label("movies").is_ok());
+    assert!(check_label("").is_err());
+    assert!(check_label("a,b").is_err());
+}
+
+#[tokio::test]
+async fn test_set_label_namespace_rejects_keys() {
+    assert!(check_namespace_key("category").is_ok());
+    // Nothing listens here, a bad key has to fail before any request
+    let mut client = Client::new("http://127.0.0.1:1/transmission/rpc");
+    for key in ["", "a:b"] {
+        assert!(matches!(
+            client
+                .set_label_namespace(Ids::Id(1), key, &["movies"])
+                .await,
+            Err(Error::WrongLabel(_))
+        ));
+    }
+}
+
+#[test]
+fn test_merge_labels_keeps_existing() {
+    let current = labels(&["a", "b"]);
+    assert_eq!(
+        merge_labels(&current, &["b", "c"]),
+        labels(&["a", "b", "c"])
+    );
+}
+
+#[test]
+fn test_strip_labels() {
+    let current = labels(&["a", "b", "c"]);
+    assert_eq!(strip_labels(&current, &["b", "x"]), labels(&["a", "c"]));
+}
+
+#[test]
+fn test_rename_label() {
+    let current = labels(&["a", "b", "c"]);
+    assert_eq!(rename_label(&current, "b", "d"), labels(&["a", "d", "c"]));
+    assert_eq!(rename_label(&current, "b", "c"), labels(&["a", "c"]));
+}
+
+#[test]
+fn test_label_namespaces_parse() {
+    let parsed = LabelNamespaces::parse(&labels(&[
+        "category:movies",
+        "owner:alice",
+        "owner:bob",
+        "keep",
+        ":odd",
+    ]));
+    assert_eq!(parsed.value("category"), Some("movies"));
+    assert_eq!(parsed.values("owner"), labels(&["alice", "bob"]).as_slice());
+    assert!(parsed.values("missing").is_empty());
+    assert_eq!(parsed.plain, labels(&["keep", ":odd"]));
+}
+
+#[test]
+fn test_set_namespace() {
+    let current = labels(&["category:tv", "keep", "category:old", "owner:alice"]);
+    assert_eq!(
+        set_namespace(&current, "category", &["movies"]),
+        labels(&["keep", "owner:alice", "category:movies"])
+    );
+    assert_eq!(
+        set_namespace(&current, "owner", &[]),
+        labels(&["category:tv", "keep", "category:old"])
+    );
+}
+
+#[test]
+fn test_namespace_filter() {
+    let parsed = LabelNamespaces::parse(&labels(&["category:movies", "owner:alice"]));
+    assert!(NamespaceFilter::Has("owner".to_string()).matches(&parsed));
+    assert!(NamespaceFilter::Missing("source".to_string()).matches(&parsed));
+    assert!(NamespaceFilter::Equals("category".to_string(), "movies".to_string()).matches(&parsed));
+    assert!(!NamespaceFilter::AnyOf(
+        "owner".to_string(),
+        vec!["bob".to_string(), "carol".to_string()]
+    )
+    .matches(&parsed));
+}
@@ -0,0 +1,118 @@
+use trpc::{
+    magnet::MagnetLink,
+    metainfo::Metainfo,
+    request::Id,
+    torrent::{Torrent, TorrentAddArgs, Tracker},
+};
+
+const HASH: &str = "6a0a9282c65fc6a1324e6e1605fe9bb9746c3aa8";
+
+#[test]
+fn test_magnet_parse() {
+    let magnet: MagnetLink = "magnet:?xt=urn:btih:6A0A9282C65FC6A1324E6E1605FE9BB9746C3AA8&dn=test%20dir&tr=http%3A%2F%2Ft%2Fann&tr.1=udp://u:80&ws=http://seed/&xl=10&so=0,2-4&x.pe=1.2.3.4:5"
+        .parse()
+        .unwrap();
+    assert_eq!(magnet.info_hash.as_deref(), Some(HASH));
+    assert_eq!(magnet.display_name.as_deref(), Some("test dir"));
+    assert_eq!(magnet.trackers, vec!["http://t/ann", "udp://u:80"]);
+    assert_eq!(magnet.web_seeds, vec!["http://seed/"]);
+    assert_eq!(magnet.exact_length, Some(10));
+    assert_eq!(magnet.select_only, vec![0, 2, 3, 4]);
+    assert_eq!(magnet.id(), Id::Hash(HASH.to_string()));
+}
+
+#[test]
+fn test_magnet_parse_base32_and_btmh() {
+    let magnet =
+        MagnetLink::try_from("magnet:?xt=urn:btih:NIFJFAWGL7DKCMSONYLAL7U3XF2GYOVI").unwrap();
+    assert_eq!(magnet.info_hash.as_deref(), Some(HASH));
+
+    let v2 = "a".repeat(64);
+    let magnet: MagnetLink = format!("magnet:?xt=urn:btmh:1220{}", v2).parse().unwrap();
+    assert_eq!(magnet.info_hash, None);
+    assert_eq!(magnet.info_hash_v2.as_deref(), Some(v2.as_str()));
+    assert_eq!(magnet.id(), Id::Hash(v2));
+}
+
+#[test]
+fn test_magnet_rejects_bad_links() {
+    assert!("http://example.com".parse::<MagnetLink>().is_err());
+    assert!("magnet:?dn=name".parse::<MagnetLink>().is_err());
+    assert!("magnet:?xt=urn:btih:1234".parse::<MagnetLink>().is_err());
+    assert!("magnet:?xt=urn:btmh:1114aaaa"
+        .parse::<MagnetLink>()
+        .is_err());
+    assert!(format!("magnet:?xt=urn:btih:{}&xl=-1", HASH)
+        .parse::<MagnetLink>()
+        .is_err());
+    assert!(format!("magnet:?xt=urn:btih:{}&dn=%zz", HASH)
+        .parse::<MagnetLink>()
+        .is_err());
+}
+
+#[test]
+fn test_magnet_rejects_oversized_select_only() {
+    for so in ["0-99999999999", "0-99999,100000", "5-3"] {
+        assert!(
+            format!("magnet:?xt=urn:btih:{}&so={}", HASH, so)
+                .parse::<MagnetLink>()
+                .is_err(),
+            "so={}",
+            so
+        );
+    }
+    let magnet: MagnetLink = format!("magnet:?xt=urn:btih:{}&so=0-99999", HASH)
+        .parse()
+        .unwrap();
+    assert_eq!(magnet.select_only.len(), 100_000);
+}
+
+#[test]
+fn test_magnet_round_trip() {
+    let magnet = MagnetLink {
+        info_hash: Some(HASH.to_string()),
+        display_name: Some("test dir".to_string()),
+        trackers: vec!["http://t/ann?a=1&b=2".to_string()],
+        select_only: vec![0, 1, 2, 5],
+        ..MagnetLink::default()
+    };
+    let link = magnet.to_string();
+    assert_eq!(
+        link,
+        format!(
+            "magnet:?xt=urn:btih:{}&dn=test%20dir&tr=http%3A%2F%2Ft%2Fann%3Fa%3D1%26b%3D2&so=0-2,5",
+            HASH
+        )
+    );
+    assert_eq!(link.parse::<MagnetLink>().unwrap(), magnet);
+
+    let args = TorrentAddArgs::from(magnet);
+    assert_eq!(args.filename, Some(link));
+    assert_eq!(args.metainfo, None);
+}
+
+#[test]
+fn test_magnet_from_metainfo_and_torrent() {
+    let metainfo = Metainfo::from_file("tests/test dir.torrent").unwrap();
+    let magnet = MagnetLink::from_metainfo(&metainfo);
+    assert_eq!(
+        magnet.to_string(),
+        format!("magnet:?xt=urn:btih:{}&dn=test%20dir&xl=10", HASH)
+    );
+
+    let torrent = Torrent {
+        hash_string: Some(HASH.to_uppercase()),
+        name: Some("test dir".to_string()),
+        trackers: Some(vec![Tracker {
+            announce: "http://t/ann".to_string(),
+            id: 0,
+            scrape: "http://t/scrape".to_string(),
+            tier: 0,
+        }]),
+        ..Torrent::default()
+    };
+    let magnet = MagnetLink::from_torrent(&torrent).unwrap();
+    assert_eq!(magnet.info_hash.as_deref(), Some(HASH));
+    assert_eq!(magnet.trackers, vec!["http://t/ann"]);
+    assert!(MagnetLink::from_torrent(&Torrent::default()).is_err());
+}
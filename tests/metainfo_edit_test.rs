@@ -0,0 +1,82 @@
+use trpc::{
+    bencode::decode,
+    metainfo::{Metainfo, MetainfoEditor},
+};
+
+mod common;
+
+use common::{HASH, TORRENT};
+
+#[test]
+fn test_edit_unchanged_round_trip() {
+    let data = std::fs::read(TORRENT).unwrap();
+    let editor = MetainfoEditor::from_bytes(&data).unwrap();
+    assert_eq!(editor.to_bytes(), data);
+    assert_eq!(editor.original_info_hash(), HASH);
+}
+
+#[test]
+fn test_edit_trackers_keeps_info_hash() {
+    let mut editor = MetainfoEditor::from_file(TORRENT).unwrap();
+    editor
+        .add_tracker("http://a/announce")
+        .add_tracker("http://b/announce")
+        .replace_tracker("http://b/announce", "http://c/announce")
+        .set_comment(Some("edited"))
+        .set_web_seeds(vec!["http://seed/".to_string()]);
+    assert!(!editor.info_hash_changed());
+    let metainfo = editor.metainfo().unwrap();
+    assert_eq!(metainfo.info_hash(), HASH);
+    assert_eq!(
+        metainfo.trackers,
+        vec![vec!["http://a/announce"], vec!["http://c/announce"]]
+    );
+    assert_eq!(metainfo.comment.as_deref(), Some("edited"));
+    assert_eq!(metainfo.web_seeds, vec!["http://seed/"]);
+
+    // Keys the editor does not know about survive
+    let root = decode(&editor.to_bytes()).unwrap();
+    assert_eq!(root.get("encoding").and_then(|v| v.as_str()), Some("UTF-8"));
+
+    editor.remove_tracker("http://a/announce");
+    let root = decode(&editor.to_bytes()).unwrap();
+    assert_eq!(
+        root.get("announce").and_then(|v| v.as_str()),
+        Some("http://c/announce")
+    );
+    assert!(root.get("announce-list").is_none());
+    editor.clear_trackers();
+    assert!(editor.trackers().is_empty());
+}
+
+#[test]
+fn test_edit_info_changes_hash() {
+    let mut editor = MetainfoEditor::from_file(TORRENT).unwrap();
+    editor.set_source(None);
+    assert!(!editor.info_hash_changed());
+    editor.set_private(true).set_source(Some("internal"));
+    assert!(editor.info_hash_changed());
+    assert_ne!(editor.info_hash(), HASH);
+
+    let dir = common::temp_dir("edit");
+    let path = dir.join("edited.torrent");
+    editor.write(&path).unwrap();
+    let metainfo = Metainfo::from_file(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(metainfo.private);
+    assert_eq!(metainfo.source.as_deref(), Some("internal"));
+    assert_eq!(metainfo.info_hash(), editor.info_hash());
+    assert!(editor.add_args().metainfo.is_some());
+}
+
+#[test]
+fn test_edit_keeps_non_canonical_info() {
+    // Unsorted info keys hash differently once re-encoded, so they are kept verbatim
+    let data = b"d4:infod6:lengthi5e12:piece lengthi16384e4:name5:a.txt6:pieces20:aaaaaaaaaaaaaaaaaaaae8:url-list5:http:e";
+    let original = Metainfo::from_bytes(data).unwrap().info_hash();
+    let mut editor = MetainfoEditor::from_bytes(data).unwrap();
+    editor
+        .set_created_by(Some("trpc"))
+        .set_creation_date(Some(1));
+    assert_eq!(editor.info_hash(), original);
+}
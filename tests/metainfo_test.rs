@@ -0,0 +1,172 @@
+use trpc::{
+    bencode::{decode, raw_dict_value, Value},
+    metainfo::{info_hash, Metainfo},
+    request::Id,
+};
+
+mod common;
+
+use common::{HASH, TORRENT};
+
+#[test]
+fn test_bencode_decode() {
+    let value = decode(b"d3:bari-42e3:fool4:spami0eee").unwrap();
+    assert_eq!(value.get("bar"), Some(&Value::Integer(-42)));
+    let list = value.get("foo").and_then(|foo| foo.as_list()).unwrap();
+    assert_eq!(list[0].as_str(), Some("spam"));
+    assert_eq!(
+        raw_dict_value(b"d1:ai1e1:bl1:xee", "b").unwrap(),
+        Some(&b"l1:xe"[..])
+    );
+}
+
+#[test]
+fn test_bencode_rejects_bad_input() {
+    assert!(decode(b"i03e").is_err());
+    assert!(decode(b"i-0e").is_err());
+    assert!(decode(b"5:abc").is_err());
+    assert!(decode(b"d1:ai1e1:ai2ee").is_err());
+    assert!(decode(b"i1ei2e").is_err());
+    assert!(decode(b"l").is_err());
+}
+
+#[test]
+fn test_metainfo_fixture() {
+    let metainfo = Metainfo::from_file(TORRENT).unwrap();
+    assert_eq!(metainfo.name, "test dir");
+    assert!(metainfo.multi_file);
+    assert_eq!(metainfo.piece_length, 32768);
+    assert_eq!(metainfo.piece_count(), 1);
+    assert_eq!(metainfo.total_size(), 10);
+    assert_eq!(
+        metainfo.file_paths(),
+        vec!["test dir/another test file.txt", "test dir/test file.txt"]
+    );
+    assert!(metainfo.trackers.is_empty());
+    assert!(!metainfo.private);
+    assert_eq!(metainfo.comment.as_deref(), Some("test torrent"));
+    assert_eq!(metainfo.creation_date, Some(1622530745));
+    assert!(metainfo
+        .created_by
+        .unwrap()
+        .starts_with("Transmission/3.00"));
+}
+
+#[test]
+fn test_metainfo_single_file_with_trackers() {
+    let data = b"d8:announce12:http://a/ann13:announce-listll12:http://a/ann12:http://b/annel12:http://c/annee8:url-list14:http://seed/x/4:infod6:lengthi5e4:name5:a.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1e6:source3:abcee";
+    let metainfo = Metainfo::from_bytes(data).unwrap();
+    assert!(!metainfo.multi_file);
+    assert_eq!(metainfo.file_paths(), vec!["a.txt"]);
+    assert_eq!(
+        metainfo.trackers,
+        vec![vec!["http://a/ann", "http://b/ann"], vec!["http://c/ann"]]
+    );
+    assert_eq!(metainfo.web_seeds, vec!["http://seed/x/"]);
+    assert!(metainfo.private);
+    assert_eq!(metainfo.source.as_deref(), Some("abc"));
+}
+
+#[test]
+fn test_metainfo_rejects_bad_pieces() {
+    let data = b"d4:infod6:lengthi5e4:name5:a.txt12:piece lengthi16384e6:pieces3:abcee";
+    assert!(Metainfo::from_bytes(data).is_err());
+    assert!(Metainfo::from_bytes(b"d4:infoi1ee").is_err());
+}
+
+#[test]
+fn test_metainfo_rejects_size_overflow() {
+    let data = format!(
+        "d4:infod6:lengthi{}e4:name5:a.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        i64::MAX
+    );
+    assert!(Metainfo::from_bytes(data.as_bytes()).is_err());
+    let data = format!(
+        "d4:infod5:filesld6:lengthi{0}e4:pathl1:aeed6:lengthi{0}e4:pathl1:beee4:name3:dir12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        i64::MAX / 2 + 1
+    );
+    assert!(Metainfo::from_bytes(data.as_bytes()).is_err());
+}
+
+#[test]
+fn test_metainfo_rejects_short_hybrid_pieces() {
+    // 100000 bytes in pieces of 16384 need 7 hashes, not 1
+    let data = b"d4:infod9:file treed5:a.txtd0:d6:lengthi100000e11:pieces root32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee6:lengthi100000e12:meta versioni2e4:name5:a.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+    assert!(Metainfo::from_bytes(data).is_err());
+}
+
+#[test]
+fn test_info_hash_v1() {
+    let data = std::fs::read(TORRENT).unwrap();
+    let metainfo = Metainfo::from_bytes(&data).unwrap();
+    assert_eq!(metainfo.info_hash_v1().as_deref(), Some(HASH));
+    assert_eq!(metainfo.info_hash_v2(), None);
+    assert_eq!(info_hash(&data).unwrap(), Id::Hash(HASH.to_string()));
+}
+
+#[test]
+fn test_info_hash_hybrid_and_v2() {
+    let hybrid = Metainfo::from_bytes(b"d4:infod9:file treed5:a.txtd0:d6:lengthi5e11:pieces root32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee6:lengthi5e12:meta versioni2e4:name5:a.txt12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee").unwrap();
+    assert_eq!(
+        hybrid.info_hash_v1().as_deref(),
+        Some("9152e706edeb8ff246371ae1d05974540577438e")
+    );
+    assert_eq!(
+        hybrid.info_hash_v2().as_deref(),
+        Some("5a914bbc56fd575754e56938219e509325bf442023de7d98b9cc441c5e40d984")
+    );
+    assert_eq!(hybrid.id(), Id::Hash(hybrid.info_hash_v1().unwrap()));
+
+    let v2 = Metainfo::from_bytes(b"d4:infod9:file treed5:a.txtd0:d6:lengthi5e11:pieces root32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee12:meta versioni2e4:name5:a.txt12:piece lengthi16384eee").unwrap();
+    assert_eq!(v2.info_hash_v1(), None);
+    assert_eq!(
+        v2.info_hash(),
+        "b35d37ee503e210adc951fd12f0b476fdcd3585475e0002354d4b04441268544"
+    );
+}
+
+fn bstr(value: &str) -> String {
+    format!("{}:{}", value.len(), value)
+}
+
+fn with_files(name: &str, path: &[&str]) -> Vec<u8> {
+    let path: String = path.iter().map(|part| bstr(part)).collect();
+    format!(
+        "d4:infod5:filesld6:lengthi5e4:pathl{}eee4:name{}12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee",
+        path,
+        bstr(name)
+    )
+    .into_bytes()
+}
+
+fn with_file_tree(key: &str) -> Vec<u8> {
+    format!(
+        "d4:infod9:file treed{}d0:d6:lengthi5e11:pieces root32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee12:meta versioni2e4:name5:a.txt12:piece lengthi16384eee",
+        bstr(key)
+    )
+    .into_bytes()
+}
+
+#[test]
+fn test_metainfo_rejects_escaping_paths() {
+    assert!(Metainfo::from_bytes(&with_files("dir", &["a", "b.txt"])).is_ok());
+    for part in ["..", "../../etc", "a/b", ".", "C:\\x", "a\0b", ""] {
+        assert!(
+            Metainfo::from_bytes(&with_files("dir", &["ok", part])).is_err(),
+            "path part {:?}",
+            part
+        );
+        assert!(
+            Metainfo::from_bytes(&with_files(part, &["a.txt"])).is_err(),
+            "name {:?}",
+            part
+        );
+        if !part.is_empty() {
+            assert!(
+                Metainfo::from_bytes(&with_file_tree(part)).is_err(),
+                "file tree key {:?}",
+                part
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use trpc::{
+    poller::{fields_with_rate, AdaptivePoller, FieldRate},
+    torrent::TorrentFields,
+};
+
+#[test]
+fn test_field_rates() {
+    assert_eq!(TorrentFields::Name.rate(), FieldRate::Static);
+    assert_eq!(TorrentFields::PieceSize.rate(), FieldRate::Static);
+    assert_eq!(TorrentFields::Labels.rate(), FieldRate::Slow);
+    assert_eq!(TorrentFields::RateDownload.rate(), FieldRate::Fast);
+    assert_eq!(TorrentFields::PercentDone.rate(), FieldRate::Fast);
+    assert_eq!(TorrentFields::Files.rate(), FieldRate::Fast);
+    assert_eq!(TorrentFields::FileStats.rate(), FieldRate::Fast);
+    let fields = [
+        TorrentFields::Name,
+        TorrentFields::RateUpload,
+        TorrentFields::TotalSize,
+    ];
+    assert_eq!(
+        fields_with_rate(&fields, FieldRate::Static),
+        vec![TorrentFields::Name, TorrentFields::TotalSize]
+    );
+}
+
+#[test]
+fn test_fields_for_poll() {
+    let poller = AdaptivePoller::new(&[
+        TorrentFields::Name,
+        TorrentFields::Labels,
+        TorrentFields::RateDownload,
+    ])
+    .slow_every(3);
+    assert_eq!(
+        poller.fields_for_poll(0),
+        vec![
+            TorrentFields::Id,
+            TorrentFields::MetadataPercentComplete,
+            TorrentFields::RateDownload,
+            TorrentFields::Name,
+            TorrentFields::Labels,
+        ]
+    );
+    assert_eq!(
+        poller.fields_for_poll(1),
+        vec![
+            TorrentFields::Id,
+            TorrentFields::MetadataPercentComplete,
+            TorrentFields::RateDownload,
+        ]
+    );
+    assert_eq!(
+        poller.fields_for_poll(3),
+        vec![
+            TorrentFields::Id,
+            TorrentFields::MetadataPercentComplete,
+            TorrentFields::RateDownload,
+            TorrentFields::Labels,
+        ]
+    );
+}
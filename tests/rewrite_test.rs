@@ -0,0 +1,153 @@
+mod common;
+
+use common::{request_body, serve_json};
+use serde_json::{json, Value};
+use trpc::{
+    client::Client,
+    rewrite::{rewrite_announce, AnnounceRewrite, RewriteSummary},
+};
+
+#[test]
+fn test_prefix_rewrite() {
+    let rule = AnnounceRewrite::prefix("http://old.example/", "https://new.example/");
+    assert_eq!(
+        rule.apply("http://old.example/abc/announce").as_deref(),
+        Some("https://new.example/abc/announce")
+    );
+    assert_eq!(rule.apply("http://other.example/announce"), None);
+}
+
+#[test]
+fn test_regex_rewrite() {
+    let rule = AnnounceRewrite::regex(r"passkey=[0-9a-f]+", "passkey=0123abcd").unwrap();
+    assert_eq!(
+        rule.apply("https://t.example/announce?passkey=deadbeef")
+            .as_deref(),
+        Some("https://t.example/announce?passkey=0123abcd")
+    );
+    assert_eq!(
+        rule.apply("https://t.example/announce?passkey=0123abcd"),
+        None
+    );
+    assert!(AnnounceRewrite::regex("(", "").is_err());
+}
+
+#[test]
+fn test_first_matching_rule_wins() {
+    let rules = vec![
+        AnnounceRewrite::prefix("udp://a/", "udp://b/"),
+        AnnounceRewrite::regex("^udp://", "http://").unwrap(),
+    ];
+    assert_eq!(
+        rewrite_announce(&rules, "udp://a/announce").as_deref(),
+        Some("udp://b/announce")
+    );
+    assert_eq!(
+        rewrite_announce(&rules, "udp://c/announce").as_deref(),
+        Some("http://c/announce")
+    );
+}
+
+#[test]
+fn test_empty_summary() {
+    let summary = RewriteSummary {
+        dry_run: true,
+        scanned: 3,
+        torrents: Vec::new(),
+    };
+    assert_eq!(
+        summary.to_string(),
+        "scanned 3 torrents, would change 0, 0 failed"
+    );
+}
+
+// A daemon with one torrent holding udp://a and udp://b in two tiers and udp://c next to b.
+// Returns the rpc url and the arguments of every torrent-set it got.
+async fn serve_torrent(rpc_version: i64) -> (String, impl Fn() -> Vec<Value>) {
+    let (url, requests) =
+        serve_json(
+            move |request| match request_body(request)["method"].as_str() {
+                Some("session-get") => json!({ "rpc-version": rpc_version }).to_string(),
+                Some("torrent-get") => json!({ "torrents": [{
+                "id": 1,
+                "name": "t",
+                "trackers": [
+                    { "announce": "udp://a/", "id": 10, "scrape": "", "tier": 0 },
+                    { "announce": "udp://b/", "id": 11, "scrape": "", "tier": 1 },
+                    { "announce": "udp://c/", "id": 12, "scrape": "", "tier": 1 },
+                ],
+            }] })
+                .to_string(),
+                _ => "{}".to_string(),
+            },
+        )
+        .await;
+    let sets = move || {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request_body(request))
+            .filter(|body| body["method"] == "torrent-set")
+            .map(|body| body["arguments"].clone())
+            .collect()
+    };
+    (url, sets)
+}
+
+fn swap_rules() -> Vec<AnnounceRewrite> {
+    vec![
+        AnnounceRewrite::prefix("udp://a/", "udp://b/"),
+        AnnounceRewrite::prefix("udp://b/", "udp://a/"),
+    ]
+}
+
+#[tokio::test]
+async fn test_rewrite_swap_with_tracker_list() {
+    let (url, sets) = serve_torrent(17).await;
+    let mut client = Client::new(&url);
+    let summary = client
+        .rewrite_announce_urls(None, &swap_rules(), true)
+        .await
+        .unwrap();
+    assert_eq!(summary.changed(), 1);
+    assert_eq!(summary.torrents[0].changes.len(), 2);
+    assert!(sets().is_empty());
+
+    client
+        .rewrite_announce_urls(None, &swap_rules(), false)
+        .await
+        .unwrap();
+    assert_eq!(sets().len(), 1);
+    assert_eq!(sets()[0]["trackerList"], "udp://b/\n\nudp://a/\nudp://c/");
+    assert!(sets()[0].get("trackerReplace").is_none());
+}
+
+#[tokio::test]
+async fn test_rewrite_legacy_replace_and_remove() {
+    let (url, sets) = serve_torrent(16).await;
+    let mut client = Client::new(&url);
+    client
+        .rewrite_announce_urls(None, &swap_rules(), false)
+        .await
+        .unwrap();
+    // c collapses into b, the first url keeps its place and the later one is removed
+    let collapse = [
+        AnnounceRewrite::prefix("udp://c/", "udp://b/"),
+        AnnounceRewrite::prefix("udp://a/", "udp://d/"),
+    ];
+    client
+        .rewrite_announce_urls(None, &collapse, false)
+        .await
+        .unwrap();
+    let sets = sets();
+    assert_eq!(sets.len(), 2);
+    assert_eq!(
+        sets[0]["trackerReplace"],
+        json!([10, "udp://b/", 11, "udp://a/"])
+    );
+    assert!(sets[0].get("trackerRemove").is_none());
+    assert!(sets[0].get("trackerList").is_none());
+    assert_eq!(sets[1]["trackerReplace"], json!([10, "udp://d/"]));
+    assert_eq!(sets[1]["trackerRemove"], json!([12]));
+}
@@ -0,0 +1,73 @@
+use trpc::{
+    schedule::{AltSpeedSchedule, TimeOfDay, Weekday, Weekdays},
+    session::Session,
+};
+
+fn time(s: &str) -> TimeOfDay {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_time_of_day_parse() {
+    assert_eq!(time("07:05").minutes(), 425);
+    assert_eq!(time("7:05").to_string(), "07:05");
+    assert!("24:00".parse::<TimeOfDay>().is_err());
+    assert!("12:60".parse::<TimeOfDay>().is_err());
+    assert!("1200".parse::<TimeOfDay>().is_err());
+    assert!(TimeOfDay::from_minutes(1440).is_err());
+}
+
+#[test]
+fn test_weekdays_bits() {
+    assert_eq!(Weekdays::from_bits(65).unwrap(), Weekdays::WEEKEND);
+    assert!(Weekdays::from_bits(128).is_err());
+    let days: Weekdays = vec![Weekday::Sunday, Weekday::Saturday]
+        .into_iter()
+        .collect();
+    assert_eq!(days, Weekdays::WEEKEND);
+    assert!(Weekdays::WEEKDAYS.contains(Weekday::Monday));
+    assert!(!Weekdays::WEEKDAYS.contains(Weekday::Sunday));
+}
+
+#[test]
+fn test_schedule_daytime_window() {
+    let schedule = AltSpeedSchedule::new(time("09:00"), time("17:00"), Weekdays::WEEKDAYS);
+    assert!(!schedule.is_overnight());
+    assert!(schedule.is_active_at(Weekday::Monday, time("09:00")));
+    assert!(!schedule.is_active_at(Weekday::Monday, time("17:00")));
+    assert!(!schedule.is_active_at(Weekday::Sunday, time("12:00")));
+}
+
+#[test]
+fn test_schedule_overnight_window() {
+    let days: Weekdays = vec![Weekday::Friday].into_iter().collect();
+    let schedule = AltSpeedSchedule::new(time("22:00"), time("06:00"), days);
+    assert!(schedule.is_overnight());
+    assert_eq!(schedule.duration_minutes(), 480);
+    assert!(schedule.is_active_at(Weekday::Friday, time("23:30")));
+    assert!(schedule.is_active_at(Weekday::Saturday, time("05:59")));
+    assert!(!schedule.is_active_at(Weekday::Saturday, time("06:00")));
+    assert!(!schedule.is_active_at(Weekday::Friday, time("05:00")));
+}
+
+#[test]
+fn test_schedule_timestamp() {
+    let schedule = AltSpeedSchedule::new(time("22:00"), time("06:00"), Weekdays::ALL);
+    // 2024-01-01 00:30:00 UTC, a Monday
+    assert!(schedule.is_active_at_timestamp(1_704_069_000, 0));
+    assert!(!schedule.is_active_at_timestamp(1_704_069_000, 8 * 60));
+}
+
+#[test]
+fn test_schedule_session_round_trip() {
+    let schedule = AltSpeedSchedule::new(time("01:15"), time("02:45"), Weekdays::WEEKEND);
+    let session = schedule.to_session();
+    assert_eq!(session.alt_speed_time_begin, Some(75));
+    assert_eq!(session.alt_speed_time_day, Some(65));
+    let parsed = AltSpeedSchedule::from_session(&session).unwrap();
+    assert_eq!(parsed, Some(schedule));
+    assert_eq!(
+        AltSpeedSchedule::from_session(&Session::default()).unwrap(),
+        None
+    );
+}
@@ -25,7 +25,7 @@ async fn test_torrent_add_uri() {
     let add_args = TorrentAddArgs::from_file(MAGNET).unwrap();
     let body = client.torrent_add(add_args).await.unwrap();
     dbg!(&body);
-    assert!(body.torrent_duplicate.is_some());
+    assert!(body.is_duplicate());
 }
 
 #[tokio::test]
@@ -68,3 +68,17 @@ async fn test_get_torrent() {
     assert!(torrent.hash_string.is_some());
     assert!(torrent.name.is_none());
 }
+
+#[tokio::test]
+async fn test_torrent_handle_refresh() {
+    let uri = dotenvy::var("TRPC_TARGET").expect("not set TRPC_TARGET");
+
+    let mut client = Client::new(&uri);
+    let mut handle = client.torrent("6a0a9282c65fc6a1324e6e1605fe9bb9746c3aa8");
+    let torrent = handle
+        .refresh(vec!["id".try_into().unwrap(), "name".try_into().unwrap()])
+        .await
+        .unwrap();
+    assert!(torrent.id.is_some());
+    assert!(torrent.hash_string.is_none());
+}
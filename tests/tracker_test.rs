@@ -0,0 +1,92 @@
+use serde_json::json;
+use trpc::{
+    request::Ids,
+    torrent::{TorrentSetArgs, Tracker},
+    tracker::{TrackerList, TrackerReplace},
+};
+
+fn tracker(id: i64, tier: i64, announce: &str) -> Tracker {
+    Tracker {
+        announce: announce.to_string(),
+        id,
+        scrape: String::new(),
+        tier,
+    }
+}
+
+#[test]
+fn test_tracker_list_parse_and_display() {
+    let list = TrackerList::parse("http://a/announce\nhttp://b/announce\n\n\nhttp://c/announce\n");
+    assert_eq!(list.tiers.len(), 2);
+    assert_eq!(list.tier_of("http://c/announce"), Some(1));
+    assert_eq!(
+        list.to_string(),
+        "http://a/announce\nhttp://b/announce\n\nhttp://c/announce"
+    );
+}
+
+#[test]
+fn test_tracker_list_from_trackers() {
+    let trackers = vec![
+        tracker(2, 1, "http://c/announce"),
+        tracker(0, 0, "http://a/announce"),
+        tracker(1, 0, "http://b/announce"),
+    ];
+    let list = TrackerList::from_trackers(&trackers);
+    assert_eq!(
+        list.tiers,
+        vec![
+            vec!["http://a/announce", "http://b/announce"],
+            vec!["http://c/announce"]
+        ]
+    );
+}
+
+#[test]
+fn test_tracker_list_edit() {
+    let mut list = TrackerList::parse("http://a/announce\n\nhttp://b/announce");
+    assert!(list.add("http://c/announce", Some(0)));
+    assert!(!list.add("http://c/announce", None));
+    assert_eq!(list.tier_of("http://c/announce"), Some(0));
+    assert!(list.replace("http://b/announce", "http://d/announce"));
+    assert!(!list.replace("http://b/announce", "http://e/announce"));
+    assert!(list.remove("http://d/announce"));
+    assert_eq!(list.tiers.len(), 1);
+    assert!(list.replace("http://a/announce", "http://c/announce"));
+    assert_eq!(list.tiers, vec![vec!["http://c/announce"]]);
+}
+
+#[test]
+fn test_tracker_replace_serialize() {
+    let args = TorrentSetArgs {
+        ids: Ids::Id(1),
+        tracker_replace: Some(TrackerReplace(vec![
+            (0, "http://a/announce".to_string()),
+            (3, "http://b/announce".to_string()),
+        ])),
+        ..TorrentSetArgs::default()
+    };
+    let value = json!(args);
+    assert_eq!(
+        value["trackerReplace"],
+        json!([0, "http://a/announce", 3, "http://b/announce"])
+    );
+    let parsed: TrackerReplace = serde_json::from_value(value["trackerReplace"].clone()).unwrap();
+    assert_eq!(parsed, args.tracker_replace.unwrap());
+}
+
+#[test]
+fn test_tracker_list_merge() {
+    let mut list = TrackerList::parse("http://a/ann\n\nhttp://b/ann");
+    let other = TrackerList::parse("http://a/ann\nhttp://c/ann\nhttp://c/ann\n\nhttp://b/ann");
+    assert!(list.merge(&other));
+    assert_eq!(
+        list.tiers,
+        vec![
+            vec!["http://a/ann"],
+            vec!["http://b/ann"],
+            vec!["http://c/ann"]
+        ]
+    );
+    assert!(!list.merge(&other));
+}
@@ -0,0 +1,63 @@
+use trpc::session::Units;
+
+fn binary_units() -> Units {
+    Units {
+        speed_units: vec![
+            "KiB/s".to_string(),
+            "MiB/s".to_string(),
+            "GiB/s".to_string(),
+            "TiB/s".to_string(),
+        ],
+        speed_bytes: 1024,
+        size_units: vec![
+            "KiB".to_string(),
+            "MiB".to_string(),
+            "GiB".to_string(),
+            "TiB".to_string(),
+        ],
+        size_bytes: 1024,
+        ..Units::default()
+    }
+}
+
+#[test]
+fn test_format_speed() {
+    let units = Units::default();
+    assert_eq!(units.format_speed(0), "0 kB/s");
+    assert_eq!(units.format_speed(512_000), "512 kB/s");
+    assert_eq!(units.format_speed(1_500_000), "1.50 MB/s");
+    assert_eq!(units.format_speed(250_000_000), "250.0 MB/s");
+    assert_eq!(units.format_speed(2_000_000_000), "2.0 GB/s");
+    assert_eq!(binary_units().format_speed(1536 * 1024), "1.50 MiB/s");
+}
+
+#[test]
+fn test_format_size() {
+    let units = Units::default();
+    assert_eq!(units.format_size(500), "0.50 kB");
+    assert_eq!(units.format_size(1_234_567), "1.23 MB");
+    assert_eq!(units.format_size(123_456_789), "123.5 MB");
+    assert_eq!(units.format_size(5_000_000_000_000_000), "5000.0 TB");
+    assert_eq!(
+        binary_units().format_size(3 * 1024 * 1024 * 1024),
+        "3.00 GiB"
+    );
+    assert_eq!(units.format_memory(64 * 1024 * 1024), "64.00 MiB");
+}
+
+#[test]
+fn test_parse_speed() {
+    let units = Units::default();
+    assert_eq!(units.parse_speed("1.5 MB/s").unwrap(), 1500);
+    assert_eq!(units.parse_speed("200kb/s").unwrap(), 200);
+    assert_eq!(binary_units().parse_speed("1.5 MiB/s").unwrap(), 1536);
+    assert!(units.parse_speed("1.5 MiB/s").is_err());
+    assert!(units.parse_speed("fast").is_err());
+}
+
+#[test]
+fn test_parse_size() {
+    let units = Units::default();
+    assert_eq!(units.parse_size("2 GB").unwrap(), 2_000_000_000);
+    assert_eq!(binary_units().parse_size("1 MiB").unwrap(), 1_048_576);
+}
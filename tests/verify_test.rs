@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+
+use trpc::{
+    create::TorrentCreator,
+    metainfo::Metainfo,
+    verify::{decode_bitfield, pack_bitfield, FileState, Verifier},
+    Error,
+};
+
+fn temp_data(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("trpc-verify-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("data")).unwrap();
+    let bytes = |len: usize, seed: u8| -> Vec<u8> {
+        (0..len)
+            .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+            .collect()
+    };
+    std::fs::write(dir.join("data").join("a.bin"), bytes(40000, 1)).unwrap();
+    std::fs::write(dir.join("data").join("b.bin"), bytes(20000, 2)).unwrap();
+    dir
+}
+
+fn metainfo(dir: &Path, hybrid: bool) -> Metainfo {
+    let data = TorrentCreator::new(dir.join("data"))
+        .piece_length(16384)
+        .hybrid(hybrid)
+        .create()
+        .unwrap();
+    Metainfo::from_bytes(&data).unwrap()
+}
+
+#[test]
+fn test_verify_fixture() {
+    let metainfo = Metainfo::from_file("tests/test dir.torrent").unwrap();
+    let report = metainfo.verify("tests").unwrap();
+    assert!(report.is_complete());
+    assert!(report
+        .files
+        .iter()
+        .all(|file| file.state == FileState::Complete));
+}
+
+#[test]
+fn test_verify_corrupt_and_missing() {
+    let dir = temp_data("damaged");
+    let metainfo = metainfo(&dir, false);
+    assert_eq!(metainfo.piece_count(), 4);
+    let report = Verifier::new(metainfo.clone(), &dir)
+        .threads(3)
+        .verify()
+        .unwrap();
+    assert_eq!(report.pieces, vec![true; 4]);
+    assert_eq!(report.pieces_base64(), "8A==");
+
+    let a = dir.join("data").join("a.bin");
+    let mut data = std::fs::read(&a).unwrap();
+    data[20000] ^= 0xff;
+    std::fs::write(&a, data).unwrap();
+    std::fs::remove_file(dir.join("data").join("b.bin")).unwrap();
+
+    let report = Verifier::new(metainfo, &dir).threads(3).verify().unwrap();
+    // Piece 2 holds the end of a.bin and the start of b.bin
+    assert_eq!(report.pieces, vec![true, false, false, false]);
+    assert_eq!(report.have_count(), 1);
+    assert_eq!(report.files[0].path, a);
+    assert_eq!(report.files[0].state, FileState::Corrupt);
+    assert_eq!(report.files[1].state, FileState::Missing);
+    assert_eq!(report.missing_files().count(), 1);
+    assert_eq!(report.corrupt_files().count(), 1);
+    assert_eq!(report.differing_pieces("8A==").unwrap(), vec![1, 2, 3]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_verify_hybrid_with_padding() {
+    let dir = temp_data("hybrid");
+    let metainfo = metainfo(&dir, true);
+    assert!(metainfo.files.iter().any(|file| file.padding));
+    std::fs::write(dir.join("data").join("b.bin"), vec![0u8; 30000]).unwrap();
+    let report = metainfo.verify(&dir).unwrap();
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.files[0].state, FileState::Complete);
+    assert_eq!(report.files[1].state, FileState::WrongSize(30000));
+    assert!(!report.is_complete());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_bitfield() {
+    let pieces = [true, false, true, true, false, false, false, false, true];
+    assert_eq!(pack_bitfield(&pieces), vec![0b1011_0000, 0b1000_0000]);
+    assert_eq!(decode_bitfield("sIA=", 9).unwrap(), pieces);
+    assert!(matches!(
+        decode_bitfield("sIA=", 20),
+        Err(Error::WrongBitfield(_))
+    ));
+}
+
+#[test]
+fn test_verify_refuses_paths_outside_download_dir() {
+    let dir = temp_data("escape");
+    let mut escaping = metainfo(&dir, false);
+    escaping.files[0].path = vec!["..".to_string(), "a.bin".to_string()];
+    assert!(matches!(
+        escaping.verify(&dir),
+        Err(Error::WrongMetainfo(_))
+    ));
+
+    #[cfg(unix)]
+    {
+        let outside = temp_data("escape-target");
+        let linked = metainfo(&dir, false);
+        std::fs::remove_file(dir.join("data").join("a.bin")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.join("data").join("a.bin"),
+            dir.join("data").join("a.bin"),
+        )
+        .unwrap();
+        assert!(matches!(linked.verify(&dir), Err(Error::WrongMetainfo(_))));
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_verify_rejects_short_piece_list() {
+    let dir = temp_data("short");
+    let mut metainfo = metainfo(&dir, true);
+    metainfo.pieces.truncate(20);
+    assert!(matches!(
+        metainfo.verify(&dir),
+        Err(Error::WrongMetainfo(_))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,40 @@
+use trpc::{
+    torrent::Torrent,
+    wait::{download_complete, location_reached, metadata_complete, verify_finished},
+};
+
+#[test]
+fn test_metadata_and_completion() {
+    let mut torrent = Torrent {
+        metadata_percent_complete: Some(0.5),
+        percent_done: Some(1.0),
+        ..Torrent::default()
+    };
+    assert!(!metadata_complete(&torrent));
+    assert!(!download_complete(&torrent));
+    torrent.metadata_percent_complete = Some(1.0);
+    assert!(metadata_complete(&torrent));
+    assert!(download_complete(&torrent));
+}
+
+#[test]
+fn test_verify_finished() {
+    let status = |status| Torrent {
+        status: Some(status),
+        ..Torrent::default()
+    };
+    assert!(!verify_finished(&status(1)));
+    assert!(!verify_finished(&status(2)));
+    assert!(verify_finished(&status(0)));
+    assert!(!verify_finished(&Torrent::default()));
+}
+
+#[test]
+fn test_location_reached() {
+    let torrent = Torrent {
+        download_dir: Some("/data/done/".to_string()),
+        ..Torrent::default()
+    };
+    assert!(location_reached(&torrent, "/data/done"));
+    assert!(!location_reached(&torrent, "/data"));
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+
+use common::{serve_rpc, temp_dir, HASH, TORRENT};
+use trpc::{
+    client::Client,
+    torrent::TorrentAddArgs,
+    watch_folder::{inbox_kind, read_magnet_file, FolderImporter, InboxKind, WatchFolder},
+    Error,
+};
+
+#[test]
+fn test_inbox_kind() {
+    assert_eq!(
+        inbox_kind(Path::new("a/b.torrent")),
+        Some(InboxKind::Torrent)
+    );
+    assert_eq!(inbox_kind(Path::new("B.TORRENT")), Some(InboxKind::Torrent));
+    assert_eq!(inbox_kind(Path::new("b.magnet")), Some(InboxKind::Magnet));
+    assert_eq!(inbox_kind(Path::new(".b.torrent")), None);
+    assert_eq!(inbox_kind(Path::new("~b.torrent")), None);
+    assert_eq!(inbox_kind(Path::new("b.torrent.part")), None);
+    assert_eq!(inbox_kind(Path::new("b.txt")), None);
+}
+
+#[test]
+fn test_read_magnet_file() {
+    let text = format!(
+        "# from the tracker\n\n  magnet:?xt=urn:btih:{}&dn=x  \n",
+        HASH
+    );
+    let magnet = read_magnet_file(&text).unwrap();
+    assert_eq!(magnet.info_hash.as_deref(), Some(HASH));
+    assert!(matches!(
+        read_magnet_file("# nothing\n"),
+        Err(Error::WrongMagnet(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_add_args_with_folder_options() {
+    let dir = temp_dir("watch-args");
+    let magnet = dir.join("a.magnet");
+    std::fs::write(&magnet, format!("magnet:?xt=urn:btih:{}", HASH)).unwrap();
+    let folder = WatchFolder::new(&dir)
+        .download_dir("/downloads/tv")
+        .label("tv")
+        .paused(true);
+    let args = folder.add_args(&magnet).await.unwrap();
+    assert_eq!(
+        args,
+        TorrentAddArgs {
+            filename: Some(format!("magnet:?xt=urn:btih:{}", HASH)),
+            download_dir: Some("/downloads/tv".to_string()),
+            paused: Some(true),
+            labels: Some(vec!["tv".to_string()]),
+            ..TorrentAddArgs::default()
+        }
+    );
+    let torrent = dir.join("b.torrent");
+    std::fs::copy(TORRENT, &torrent).unwrap();
+    assert!(folder.add_args(&torrent).await.unwrap().metainfo.is_some());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_scan_waits_for_stable_files() {
+    let dir = temp_dir("watch-scan");
+    let mut importer = FolderImporter::new(Duration::from_secs(1)).folder(WatchFolder::new(&dir));
+    let torrent = dir.join("a.torrent");
+    std::fs::write(&torrent, b"d4:info").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"x").unwrap();
+    std::fs::write(dir.join("empty.torrent"), b"").unwrap();
+    assert!(importer.scan().await.0.is_empty());
+    assert_eq!(importer.scan().await.0, vec![(0, torrent.clone())]);
+    std::fs::write(&torrent, b"d4:infod").unwrap();
+    assert!(importer.scan().await.0.is_empty());
+    assert_eq!(importer.scan().await.0, vec![(0, torrent)]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_missing_folder_does_not_stop_the_others() {
+    let dir = temp_dir("watch-missing");
+    let missing = dir.join("missing");
+    let mut importer = FolderImporter::new(Duration::from_secs(1))
+        .folder(WatchFolder::new(&missing))
+        .folder(WatchFolder::new(&dir));
+    std::fs::copy(TORRENT, dir.join("a.torrent")).unwrap();
+    let mut client = Client::new(&serve_rpc(|_| "torrent-added").await);
+
+    let batch = importer.poll(&mut client).await;
+    assert_eq!(batch.folder_errors.len(), 1);
+    assert_eq!(batch.folder_errors[0].folder, missing);
+    assert!(matches!(batch.folder_errors[0].error, Error::Io(_)));
+    let batch = importer.poll(&mut client).await;
+    assert!(batch.error.is_none());
+    assert_eq!(batch.folder_errors.len(), 1);
+    assert_eq!(batch.events.len(), 1);
+    assert!(batch.events[0].result.is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_poll_moves_files() {
+    let dir = temp_dir("watch-poll");
+    let done = dir.join("imported");
+    let mut importer =
+        FolderImporter::new(Duration::from_secs(1)).folder(WatchFolder::new(&dir).done_dir(&done));
+    std::fs::copy(TORRENT, dir.join("good.torrent")).unwrap();
+    std::fs::write(dir.join("bad.torrent"), b"<html></html>").unwrap();
+    let mut client = Client::new(&serve_rpc(|_| "torrent-added").await);
+
+    assert!(importer.poll(&mut client).await.events.is_empty());
+    let batch = importer.poll(&mut client).await;
+    assert!(batch.error.is_none());
+    let events = batch.events;
+    assert_eq!(events.len(), 2);
+
+    let bad = &events[0];
+    assert_eq!(bad.path, dir.join("bad.torrent"));
+    assert_eq!(bad.moved_to, Some(dir.join("failed").join("bad.torrent")));
+    assert!(matches!(bad.result, Err(Error::Bencode(_))));
+    assert!(dir.join("failed").join("bad.torrent.error").exists());
+
+    let good = &events[1];
+    assert_eq!(good.moved_to, Some(done.join("good.torrent")));
+    assert!(!good.result.as_ref().unwrap().is_duplicate());
+    assert!(!dir.join("good.torrent").exists());
+
+    assert!(importer.poll(&mut client).await.events.is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_poll_keeps_events_before_connection_error() {
+    let dir = temp_dir("watch-offline");
+    let mut importer = FolderImporter::new(Duration::from_secs(1)).folder(WatchFolder::new(&dir));
+    // Not utf-8, so reading the magnet fails
+    std::fs::write(dir.join("a.magnet"), [0xff, 0xfe]).unwrap();
+    std::fs::copy(TORRENT, dir.join("b.torrent")).unwrap();
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/transmission/rpc", closed.local_addr().unwrap());
+    drop(closed);
+    let mut client = Client::new(&url);
+
+    assert!(importer.poll(&mut client).await.events.is_empty());
+    let batch = importer.poll(&mut client).await;
+    assert_eq!(batch.events.len(), 1);
+    assert!(matches!(batch.events[0].result, Err(Error::Io(_))));
+    assert_eq!(
+        batch.events[0].moved_to,
+        Some(dir.join("failed").join("a.magnet"))
+    );
+    assert!(matches!(batch.error, Some(Error::Nc(_))));
+    assert!(dir.join("b.torrent").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::{request_body, serve_json};
+use trpc::{
+    client::Client,
+    torrent::Torrent,
+    watcher::{diff_torrent, TorrentEvent, Watcher},
+};
+
+fn torrent(status: i64, percent_done: f64) -> Torrent {
+    Torrent {
+        id: Some(1),
+        status: Some(status),
+        percent_done: Some(percent_done),
+        error: Some(0),
+        labels: Some(vec!["a".to_string()]),
+        download_dir: Some("/downloads".to_string()),
+        ..Torrent::default()
+    }
+}
+
+fn kinds(events: &[TorrentEvent]) -> Vec<&'static str> {
+    events
+        .iter()
+        .map(|event| match event {
+            TorrentEvent::Added(_) => "added",
+            TorrentEvent::Removed(_) => "removed",
+            TorrentEvent::Started(_) => "started",
+            TorrentEvent::Stopped(_) => "stopped",
+            TorrentEvent::Completed(_) => "completed",
+            TorrentEvent::Verified(_) => "verified",
+            TorrentEvent::ErrorRaised(_) => "error raised",
+            TorrentEvent::ErrorCleared(_) => "error cleared",
+            TorrentEvent::LabelsChanged { .. } => "labels changed",
+            TorrentEvent::Moved { .. } => "moved",
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff_unchanged() {
+    assert!(diff_torrent(&torrent(4, 0.5), &torrent(4, 0.6)).is_empty());
+}
+
+#[test]
+fn test_diff_start_stop_complete() {
+    assert_eq!(
+        kinds(&diff_torrent(&torrent(0, 0.5), &torrent(4, 0.5))),
+        vec!["started"]
+    );
+    assert_eq!(
+        kinds(&diff_torrent(&torrent(4, 0.9), &torrent(6, 1.0))),
+        vec!["completed"]
+    );
+    assert_eq!(
+        kinds(&diff_torrent(&torrent(6, 1.0), &torrent(0, 1.0))),
+        vec!["stopped"]
+    );
+}
+
+#[test]
+fn test_diff_verified() {
+    assert_eq!(
+        kinds(&diff_torrent(&torrent(2, 0.3), &torrent(0, 0.3))),
+        vec!["verified"]
+    );
+    assert_eq!(
+        kinds(&diff_torrent(&torrent(2, 0.3), &torrent(4, 0.3))),
+        vec!["verified"]
+    );
+    assert!(diff_torrent(&torrent(0, 0.3), &torrent(1, 0.3)).is_empty());
+    assert!(diff_torrent(&torrent(1, 0.3), &torrent(2, 0.3)).is_empty());
+}
+
+#[test]
+fn test_diff_error_labels_moved() {
+    let old = torrent(4, 0.5);
+    let mut new = torrent(4, 0.5);
+    new.error = Some(2);
+    new.labels = Some(vec!["b".to_string()]);
+    new.download_dir = Some("/archive".to_string());
+    let events = diff_torrent(&old, &new);
+    assert_eq!(
+        kinds(&events),
+        vec!["error raised", "labels changed", "moved"]
+    );
+    assert_eq!(
+        events[2],
+        TorrentEvent::Moved {
+            torrent: new.clone(),
+            old: "/downloads".to_string()
+        }
+    );
+    assert_eq!(kinds(&diff_torrent(&new, &old))[0], "error cleared");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_poll_added_removed() {
+    let polls = AtomicUsize::new(0);
+    let (url, requests) = serve_json(move |_| {
+        let torrents = match polls.fetch_add(1, Ordering::SeqCst) {
+            0 => r#"{"torrents":[{"id":1,"status":0},{"id":2,"status":0}]}"#,
+            1 => r#"{"torrents":[{"id":2,"status":4},{"id":3,"status":4}],"removed":[1]}"#,
+            _ => r#"{"torrents":[{"id":3,"status":4}]}"#,
+        };
+        torrents.to_string()
+    })
+    .await;
+    let mut watcher = Watcher::new(Client::new(&url), Duration::from_secs(120));
+    assert!(watcher.poll().await.unwrap().is_empty());
+    let events = watcher.poll().await.unwrap();
+    assert_eq!(kinds(&events), vec!["started", "added", "removed"]);
+    assert_eq!(events[1].torrent().id, Some(3));
+    assert_eq!(events[2].torrent().id, Some(1));
+
+    // Past the daemon's recently-active window only a full list is complete
+    tokio::time::advance(Duration::from_secs(120)).await;
+    let events = watcher.poll().await.unwrap();
+    assert_eq!(kinds(&events), vec!["removed"]);
+    assert_eq!(events[0].torrent().id, Some(2));
+
+    let ids: Vec<_> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request_body(request)["arguments"]["ids"].clone())
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            serde_json::Value::Null,
+            serde_json::json!("recently-active"),
+            serde_json::Value::Null
+        ]
+    );
+}